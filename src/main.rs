@@ -1,20 +1,61 @@
-use clap::{Parser, Subcommand};
+use std::{
+    io::{IsTerminal, Read},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, FixedOffset, Local};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use regex::Regex;
 use utils::date::Date;
 
 mod utils;
-use crate::utils::{date::parse_date, todolist::ListFile};
+use crate::utils::{
+    date::{parse_date, parse_since},
+    editor::{edit_text, parse_task_document, task_document},
+    history::{History, DEFAULT_HISTORY_DEPTH},
+    lock::{ListFileLock, LOCK_TIMEOUT},
+    paths::{errors::PathError, project_file_path, resolve_store, Store, StoreKind},
+    priority::Priority,
+    recur::{parse_interval, Interval, RecurFrom, Recurrence},
+    schema::{version_of, CURRENT_VERSION},
+    selector::{parse_selector, Selector},
+    status::Status,
+    tags::normalize_tag,
+    task_ref::{parse_task_path, parse_task_ref, TaskPath, TaskRef},
+    todolist::{
+        confirm_list_deletion, errors::ListError, ListFile, SortKey, TaskChanges, TaskFilter,
+    },
+    trash::DEFAULT_TRASH_DAYS,
+};
 
 #[derive(Debug, Clone, Parser)]
 #[command(name = "Todo", author = "Ayhan Eyikan", version, about)]
 #[allow(clippy::upper_case_acronyms)]
 struct CLI {
+    /// Path of the todolists file to use instead of the default location
+    #[arg(short, long, global = true, env = "TODO_FILE")]
+    file: Option<PathBuf>,
+
+    /// Name of the list to use instead of the focused one, without changing focus
+    #[arg(short, long, global = true)]
+    list: Option<String>,
+
+    /// Number of commands kept in the history for `undo-last`
+    #[arg(long, global = true, env = "TODO_HISTORY_DEPTH", default_value_t = DEFAULT_HISTORY_DEPTH)]
+    history_depth: usize,
+
+    /// Number of days dropped tasks and deleted lists are kept in the trash
+    #[arg(long, global = true, env = "TODO_TRASH_DAYS", default_value_t = DEFAULT_TRASH_DAYS)]
+    trash_days: u32,
+
     #[command(subcommand)]
     command: Command,
 }
 
 #[derive(Debug, Clone, Subcommand)]
 enum Command {
+    /// Create a todolists file for the project in the current directory
+    Init,
     /// Create new list
     Create {
         /// Name of the new list
@@ -31,21 +72,81 @@ enum Command {
         name: String,
     },
     /// List of existing todolists
-    List,
+    List {
+        /// Include archived lists
+        #[arg(short, long)]
+        archived: bool,
+    },
     /// List of existing todolists
-    Ls,
+    Ls {
+        /// Include archived lists
+        #[arg(short, long)]
+        archived: bool,
+    },
+    /// Rename existing list
+    Rename {
+        /// Current name of the list
+        old: String,
+        /// New name for the list
+        new: String,
+    },
+    /// Create a new list as a copy of an existing one
+    Clone {
+        /// Name of list to copy
+        source: String,
+        /// Name of the new list
+        name: String,
+
+        /// Mark every task in the new list as not started
+        #[arg(short, long)]
+        reset: bool,
+    },
+    /// Hide a finished list without deleting it
+    Archive {
+        /// Name of list to archive
+        name: String,
+    },
+    /// Restore an archived list
+    Unarchive {
+        /// Name of list to restore
+        name: String,
+    },
 
     /// Lists tasks within focused todolist
     Tasks {
         /// List tasks from all todolists
         #[arg(short, long)]
         all: bool,
+
+        /// Only list tasks with the given tag(s)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Only list tasks marked done since the given date, e.g. monday, -2w or 10/01
+        #[arg(long, value_name = "DATE", value_parser = parse_since, allow_hyphen_values = true)]
+        done_since: Option<DateTime<FixedOffset>>,
+
+        /// Order in which tasks are listed
+        #[arg(short, long, value_enum, default_value_t)]
+        sort: SortKey,
     },
     /// Lists tasks within focused todolist
     Ts {
         /// List tasks from all todolists
         #[arg(short, long)]
         all: bool,
+
+        /// Only list tasks with the given tag(s)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Only list tasks marked done since the given date, e.g. monday, -2w or 10/01
+        #[arg(long, value_name = "DATE", value_parser = parse_since, allow_hyphen_values = true)]
+        done_since: Option<DateTime<FixedOffset>>,
+
+        /// Order in which tasks are listed
+        #[arg(short, long, value_enum, default_value_t)]
+        sort: SortKey,
     },
     /// Add a task to the focused todolist
     Add {
@@ -56,33 +157,223 @@ enum Command {
         /// Task(s) due date
         #[arg(short, long, value_parser = parse_date)]
         date: Option<Date>,
+
+        /// Task(s) priority
+        #[arg(short, long, value_enum)]
+        priority: Option<Priority>,
+
+        /// Tag(s) for the task(s), in addition to any +tag or #tag words in their titles
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Repeat the task(s): day, week, month, weekday, 3d, 2w, 6m, 1st-monday, last-friday, ...
+        #[arg(short, long, value_parser = parse_interval)]
+        every: Option<Interval>,
+
+        /// Schedule repeats from the completion date rather than the due date
+        #[arg(long, requires = "every")]
+        from_completion: bool,
+
+        /// Add the task(s) as subtasks of the task with this ID
+        #[arg(long, value_parser = parse_task_path)]
+        parent: Option<TaskPath>,
+
+        /// ID(s) of task(s) which must be completed before the new task(s)
+        #[arg(short, long, value_name = "ID", value_parser = parse_task_path)]
+        after: Vec<TaskPath>,
+    },
+    /// Lists tasks in the focused todolist which can be worked on now
+    Next,
+    /// Writes notes for a task in $EDITOR, or reads them from stdin when it is piped
+    Note {
+        /// ID of task to write notes for, e.g. 3, 3.1 for a subtask or work:3
+        #[arg(value_parser = parse_task_ref)]
+        id: TaskRef,
+
+        /// Remove the task's notes
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Shows the details and notes of a task
+    Show {
+        /// ID of task to show, e.g. 3, 3.1 for a subtask or work:3
+        #[arg(value_parser = parse_task_ref)]
+        id: TaskRef,
     },
     /// Drops given task(s) from the focused todolist
     Drop {
-        /// Index(ices) of task(s) to delete from the focused list
-        #[arg(required = true)]
-        index: Vec<usize>,
+        #[command(flatten)]
+        selection: TaskSelection,
     },
     /// Marks given task(s) as complete
     Done {
-        /// Index(ices) of task(s) to mark as complete
-        #[arg(required = true)]
-        index: Vec<usize>,
+        #[command(flatten)]
+        selection: TaskSelection,
+
+        /// Also complete parent tasks once all of their subtasks are complete
+        #[arg(short, long)]
+        complete_parents: bool,
     },
     /// Marks given task(s) as complete
     Do {
-        /// Index(ices) of task(s) to mark as complete
-        #[arg(required = true)]
-        index: Vec<usize>,
+        #[command(flatten)]
+        selection: TaskSelection,
+
+        /// Also complete parent tasks once all of their subtasks are complete
+        #[arg(short, long)]
+        complete_parents: bool,
     },
-    /// Marks given task(s) as incomplete
+    /// Marks given task(s) as not started
     Undo {
-        /// Index(ices) of task(s) to mark as incomplete
-        #[arg(required = true)]
-        index: Vec<usize>,
+        #[command(flatten)]
+        selection: TaskSelection,
+    },
+    /// Marks given task(s) as in progress
+    Start {
+        #[command(flatten)]
+        selection: TaskSelection,
+    },
+    /// Marks given task(s) as waiting on something outside the list
+    Wait {
+        #[command(flatten)]
+        selection: TaskSelection,
+    },
+    /// Marks given task(s) as blocked
+    Block {
+        #[command(flatten)]
+        selection: TaskSelection,
+    },
+    /// Marks given task(s) as cancelled
+    Cancel {
+        #[command(flatten)]
+        selection: TaskSelection,
+    },
+    /// Moves task(s) from the focused todolist to another list
+    #[command(allow_missing_positional = true)]
+    Mv {
+        /// ID(s) of task(s) to move, e.g. 3, 3.1, 1-5, done, /regex/, tag:x or work:3.
+        /// May be left out when moving completed tasks with --done
+        #[arg(required_unless_present = "done", value_parser = parse_selector)]
+        id: Vec<Selector>,
+
+        /// Name of the list to move the task(s) to
+        #[arg(value_name = "LIST")]
+        to: String,
+
+        /// Move every completed top-level task, in addition to any given IDs
+        #[arg(short, long)]
+        done: bool,
+
+        /// Show which tasks would be affected without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Copies task(s) from the focused todolist to another list
+    Cp {
+        /// ID(s) of task(s) to copy, e.g. 3, 3.1, 1-5, done, /regex/, tag:x or work:3
+        #[arg(required = true, value_parser = parse_selector)]
+        id: Vec<Selector>,
+
+        /// Name of the list to copy the task(s) to
+        #[arg(value_name = "LIST")]
+        to: String,
+
+        /// Show which tasks would be affected without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Lists recent commands which can be undone
+    History,
+    /// Reverts the most recent command which changed your lists
+    UndoLast,
+    /// Re-applies the most recently undone command
+    Redo,
+    /// Lists dropped tasks and deleted lists which can be restored
+    Trash {
+        #[command(subcommand)]
+        action: Option<TrashAction>,
+    },
+    /// Puts a dropped task or deleted list back where it was
+    Restore {
+        /// ID of the item in the trash
+        id: u32,
+    },
+    /// Changes the title, due date, priority or dependencies of a task
+    #[command(group(ArgGroup::new("changes").required(true).multiple(true)))]
+    Edit {
+        /// ID of task to edit, e.g. 3, 3.1 for a subtask or work:3
+        #[arg(value_parser = parse_task_ref)]
+        id: TaskRef,
+
+        /// New title for the task
+        #[arg(short, long, group = "changes")]
+        title: Option<String>,
+
+        /// New due date for the task
+        #[arg(short, long, value_parser = parse_date, group = "changes", conflicts_with = "clear_date")]
+        date: Option<Date>,
+
+        /// Remove the task's due date
+        #[arg(long, group = "changes")]
+        clear_date: bool,
+
+        /// New priority for the task
+        #[arg(
+            short,
+            long,
+            value_enum,
+            group = "changes",
+            conflicts_with = "clear_priority"
+        )]
+        priority: Option<Priority>,
+
+        /// Remove the task's priority
+        #[arg(long, group = "changes")]
+        clear_priority: bool,
+
+        /// ID(s) of task(s) which must be completed before this one
+        #[arg(
+            short,
+            long,
+            value_name = "ID",
+            value_parser = parse_task_path,
+            group = "changes",
+            conflicts_with = "clear_after"
+        )]
+        after: Vec<TaskPath>,
+
+        /// Remove all of the task's dependencies
+        #[arg(long, group = "changes")]
+        clear_after: bool,
+
+        /// Edit the task as a text document in $EDITOR
+        #[arg(short, long, group = "changes", conflicts_with_all = ["title", "date", "clear_date", "priority", "clear_priority", "after", "clear_after"])]
+        editor: bool,
     },
 }
 
+#[derive(Debug, Clone, Subcommand)]
+enum TrashAction {
+    /// Permanently deletes everything in the trash
+    Empty,
+}
+
+/// Tasks picked by commands which act on many tasks at once
+#[derive(Debug, Clone, Args)]
+struct TaskSelection {
+    /// ID(s) of task(s) to act on, e.g. 3, 3.1, 1-5, done, /regex/, tag:x or work:3
+    #[arg(required = true, value_parser = parse_selector)]
+    id: Vec<Selector>,
+
+    /// Interpret arguments as positions of top-level tasks in the displayed list instead of IDs
+    #[arg(short, long)]
+    position: bool,
+
+    /// Show which tasks would be affected without changing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
 /*
 
    MINI DEV LIST
@@ -92,7 +383,7 @@ enum Command {
 
    `todo` maybe should display the focused list rather than help info
 
-   
+
    creating new list should focus it
    create github actions which automate testing and binary release (on push to main)
    add tests for todolist and date modules
@@ -115,37 +406,397 @@ fn ensure_valid_list_name(name: &String) {
     }
 }
 
+/// Resolves selectors into the tasks they pick, grouped by the name of the list they are in, in the
+/// order the lists are first mentioned. Selectors without a list refer to `default`, or the focused
+/// list if that is None. If `position` is set, IDs are positions in the displayed list instead.
+/// Exits with an error if the selectors pick out no tasks at all
+fn select_tasks(
+    list_file: &ListFile,
+    selectors: Vec<Selector>,
+    default: Option<&str>,
+    position: bool,
+) -> Vec<(String, Vec<TaskPath>)> {
+    let mut groups: Vec<(String, Vec<TaskPath>)> = Vec::new();
+    for selector in selectors {
+        // retrieve TodoList the tasks are in
+        let list = match list_file.get_list(selector.list.as_deref().or(default)) {
+            Ok(list) => list,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1)
+            }
+        };
+
+        let paths = match selector.resolve(list, position) {
+            Ok(paths) => paths,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1)
+            }
+        };
+        match groups.iter_mut().find(|(name, _)| *name == list.name) {
+            Some((_, selected)) => {
+                for path in paths {
+                    if !selected.contains(&path) {
+                        selected.push(path);
+                    }
+                }
+            }
+            None => groups.push((list.name.clone(), paths)),
+        }
+    }
+
+    if groups.iter().all(|(_, paths)| paths.is_empty()) {
+        eprintln!("Error: {}", ListError::NoMatchingTasks);
+        std::process::exit(1)
+    }
+    groups
+}
+
+/// Prints the tasks a command would change, headed by what it would do to them
+fn preview(list_file: &ListFile, groups: &[(String, Vec<TaskPath>)], action: &str) {
+    println!("Would {}:", action);
+    for (name, paths) in groups {
+        for path in paths {
+            match list_file.lists[name].get_task(path) {
+                Ok(task) => println!("  {}:{}| {}", name, path, task),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1)
+                }
+            }
+        }
+    }
+}
+
+/// Moves the given tasks to a new status and saves the change.
+/// If `complete_parents` is set, parents whose subtasks are all finished are marked done too
+fn set_statuses(
+    store: &Store,
+    list_name: Option<&str>,
+    selection: TaskSelection,
+    status: Status,
+    complete_parents: bool,
+) {
+    // read in listfile
+    let mut list_file = ListFile::from_file(&store.path);
+
+    let groups = select_tasks(&list_file, selection.id, list_name, selection.position);
+    let status_name = status.to_possible_value().unwrap();
+    let status_name = status_name.get_name();
+    if selection.dry_run {
+        preview(&list_file, &groups, &format!("mark as {}", status_name));
+        return;
+    }
+
+    let mut num_changed = 0;
+    for (name, paths) in groups {
+        // update task statuses
+        let list = list_file.lists.get_mut(&name).unwrap();
+        let updates = match list.update_statuses(paths, status, complete_parents) {
+            Ok(updates) => updates,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1)
+            }
+        };
+
+        // report each task which changed, and any occurrences of recurring tasks added or removed
+        for path in &updates.changed {
+            if let Ok(t) = list.get_task(path) {
+                println!("Marked {}| {} as {}", path, t, status_name);
+            }
+        }
+        for (path, t) in updates.spawned {
+            println!("Added next occurrence {}| {}", path, t);
+        }
+        for (path, t) in updates.withdrawn {
+            println!("Removed next occurrence {}| {}", path, t);
+        }
+        for path in updates.kept {
+            eprintln!(
+                "Warning: the next occurrence of {} has been changed or removed since it was added, so it was left alone and {} no longer repeats",
+                path, path
+            );
+        }
+        num_changed += updates.changed.len();
+    }
+    if num_changed == 0 {
+        println!(
+            "No tasks changed, the selected tasks are already {}",
+            status_name
+        );
+    }
+
+    // write todolist file
+    list_file.to_file(&store.path);
+}
+
+/// Archives or restores the given list and saves the change
+fn set_archived(store: &Store, name: &String, archived: bool) {
+    ensure_valid_list_name(name);
+
+    // read in listfile
+    let mut list_file = ListFile::from_file(&store.path);
+
+    // archive or restore list
+    match list_file.set_archived(name, archived) {
+        Ok(_) if archived => println!("Archived todolist '{}'", name),
+        Ok(_) => println!("Restored todolist '{}'", name),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1)
+        }
+    }
+
+    // write todolist file
+    list_file.to_file(&store.path);
+}
+
+/// Moves or copies the given tasks to another list and saves the change.
+/// If `done` is set, every completed top-level task of the default list is moved too
+fn transfer_tasks(
+    store: &Store,
+    list_name: Option<&str>,
+    id: Vec<Selector>,
+    to: &str,
+    done: bool,
+    copy: bool,
+    dry_run: bool,
+) {
+    // read in listfile
+    let mut list_file = ListFile::from_file(&store.path);
+
+    // include completed tasks if requested
+    let mut id = id;
+    if done {
+        let from = match list_file.get_list(list_name) {
+            Ok(list) => list,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1)
+            }
+        };
+        let completed = from.tasks.iter().filter(|t| t.status == Status::Done);
+        id.extend(completed.map(|t| {
+            Selector::from(TaskRef {
+                list: Some(from.name.clone()),
+                path: TaskPath::top(t.id),
+            })
+        }));
+    }
+
+    let groups = select_tasks(&list_file, id, list_name, false);
+    if dry_run {
+        let verb = if copy { "copy" } else { "move" };
+        preview(&list_file, &groups, &format!("{} to '{}'", verb, to));
+        return;
+    }
+
+    let verb = if copy { "Copied" } else { "Moved" };
+    for (from, paths) in groups {
+        let transferred = match list_file.transfer_tasks(&from, paths, to, copy) {
+            Ok(transferred) => transferred,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1)
+            }
+        };
+
+        // report the new ID of each task
+        for (old, new) in transferred {
+            if let Ok(task) = list_file.lists[to].get_task(&new) {
+                println!(
+                    "{} task {}:{} to '{}' as {}| {}",
+                    verb, from, old, to, new, task
+                );
+            }
+        }
+    }
+
+    // write todolist file
+    list_file.to_file(&store.path);
+}
+
+/// Runs `f`, such as opening $EDITOR or a prompt, without holding the lock, so other `todo`
+/// commands can use the lists meanwhile. Once the lock is back, `before` is refreshed so the
+/// history only records this command's change
+fn unlocked<T, E: std::fmt::Display>(
+    store: &Store,
+    lock: &ListFileLock,
+    before: &mut Option<serde_json::Value>,
+    f: impl FnOnce() -> Result<T, E>,
+) -> T {
+    let result = match lock.unlocked(LOCK_TIMEOUT, f) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1)
+        }
+    };
+    *before = ListFile::read_raw(&store.path).ok();
+    match result {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1)
+        }
+    }
+}
+
+/// The command line as it was entered, e.g. `todo drop 1 2`
+fn command_line() -> String {
+    let args = std::env::args().skip(1).map(|arg| {
+        if arg.is_empty() || arg.contains(char::is_whitespace) {
+            format!("{:?}", arg)
+        } else {
+            arg
+        }
+    });
+    std::iter::once("todo".to_string())
+        .chain(args)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Adds the current command to the history if it changed the lists from `before`.
+/// Failing to keep the history never fails the command itself
+fn record_history(store: &Store, before: serde_json::Value, depth: usize) {
+    // upgrading an older file is not a change worth undoing
+    let after = ListFile::read_raw(&store.path).ok();
+    if after.as_ref() == Some(&before) || version_of(&before) != CURRENT_VERSION {
+        return;
+    }
+
+    let result = History::load(&store.path).and_then(|mut history| {
+        history.record(command_line(), before, depth);
+        history.save(&store.path)
+    });
+    if let Err(e) = result {
+        eprintln!("Warning: {}", e);
+    }
+}
+
+/// Undoes the latest command, or redoes the latest undone command, and saves the change
+fn restore_history(store: &Store, redo: bool) {
+    let mut history = match History::load(&store.path) {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1)
+        }
+    };
+    let current = match ListFile::read_raw(&store.path) {
+        Ok(current) => current,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1)
+        }
+    };
+
+    // swap the current lists for the snapshot on the other side of the command
+    let step = if redo {
+        history.redo(current)
+    } else {
+        history.undo(current)
+    };
+    let (command, snapshot) = match step {
+        Ok(step) => step,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1)
+        }
+    };
+    let list_file = match ListFile::from_value(snapshot, &store.path) {
+        Ok((list_file, _)) => list_file,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1)
+        }
+    };
+
+    // write todolist file, then the history which now points past it
+    list_file.to_file(&store.path);
+    if let Err(e) = history.save(&store.path) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1)
+    }
+    println!("{} `{}`", if redo { "Redid" } else { "Undid" }, command);
+}
+
 fn main() {
-    // attempt to retrieve a path to the todolists file within the user's home directory
-    let todolists_path = match home::home_dir() {
-        Some(home_path) if !home_path.as_os_str().is_empty() => {
-            format!("{}/.todolists", home_path.display())
+    // parse user command passed in before touching any files
+    let cli = CLI::parse();
+
+    // determine which todolists file to use
+    // `init` creates a project store in the current directory rather than looking one up
+    let store = match cli.command {
+        Command::Init => project_file_path().map(|path| Store {
+            path: path.display().to_string(),
+            kind: StoreKind::Project,
+        }),
+        _ => resolve_store(cli.file),
+    };
+    let store = match store {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1)
         }
-        _ => {
-            eprintln!("Error: could not locate your home directory");
+    };
+
+    // hold the lock for the whole read-modify-write cycle of the command
+    let lock = match ListFileLock::acquire(&store.path, LOCK_TIMEOUT) {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("Error: {}", e);
             std::process::exit(1)
         }
     };
 
-    // initialize .todolists file if it doesn't already exist
-    if !std::path::Path::new(&todolists_path).exists() {
-        ListFile::new().to_file(&todolists_path);
+    if let Command::Init = cli.command {
+        if std::path::Path::new(&store.path).exists() {
+            eprintln!(
+                "Error: {}",
+                PathError::StoreExists {
+                    path: store.path.clone()
+                }
+            );
+            std::process::exit(1)
+        }
+        ListFile::new().to_file(&store.path);
+        println!("Initialized project todolists in {}", store.path);
+        return;
+    }
+
+    // initialize todolists file if it doesn't already exist
+    if !std::path::Path::new(&store.path).exists() {
+        ListFile::new().to_file(&store.path);
     }
 
-    const NO_LISTS_MSG: &str = "You have no lists, use `todo create <list-name>` to create one.";
+    // list named with --list, used in place of the focused list
+    let list_name = cli.list.as_deref();
 
-    //
-    // parse user command passed in
+    // remember the lists as they were, so changes made by the command can be undone
+    let journaled = !matches!(
+        cli.command,
+        Command::History | Command::UndoLast | Command::Redo
+    );
+    let mut before = ListFile::read_raw(&store.path).ok();
 
-    match CLI::parse().command {
+    const NO_LISTS_MSG: &str = "You have no lists, use `todo create <list-name>` to create one.";
+
+    match cli.command {
         //
         // LIST_FILE COMMANDS
         //
+        Command::Init => {} // handled above, before the store is initialized
+
         Command::Create { name } => {
             ensure_valid_list_name(&name);
 
             // read in listfile
-            let mut list_file = ListFile::from_file(&todolists_path);
+            let mut list_file = ListFile::from_file(&store.path);
 
             // attempt to create list
             match list_file.create_list(&name) {
@@ -157,18 +808,26 @@ fn main() {
             }
 
             // write todolist file
-            list_file.to_file(&todolists_path);
+            list_file.to_file(&store.path);
         }
 
         Command::Delete { name } => {
             ensure_valid_list_name(&name);
 
-            // read in listfile
-            let mut list_file = ListFile::from_file(&todolists_path);
+            // confirm that the list exists before asking, then ask without holding the lock
+            if let Err(e) = ListFile::from_file(&store.path).get_list(Some(&name)) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1)
+            }
+            unlocked(&store, &lock, &mut before, || confirm_list_deletion(&name));
+
+            // read in listfile, which may have changed while waiting for confirmation
+            let mut list_file = ListFile::from_file(&store.path);
 
             // delete desired list
+            list_file.purge_trash(cli.trash_days);
             match list_file.delete_list(&name) {
-                Ok(_) => println!("Successfully deleted '{}'", name),
+                Ok(trash_id) => println!("Successfully deleted '{}' (trash ID {})", name, trash_id),
                 Err(e) => {
                     eprintln!("Error: {}", e);
                     std::process::exit(1)
@@ -176,14 +835,14 @@ fn main() {
             }
 
             // write todolist file
-            list_file.to_file(&todolists_path);
+            list_file.to_file(&store.path);
         }
 
         Command::Focus { name } => {
             ensure_valid_list_name(&name);
 
             // read in listfile
-            let mut list_file = ListFile::from_file(&todolists_path);
+            let mut list_file = ListFile::from_file(&store.path);
 
             // shift focus
             if let Err(e) = list_file.shift_focus(&name) {
@@ -192,12 +851,59 @@ fn main() {
             }
 
             // write todolist file
-            list_file.to_file(&todolists_path);
+            list_file.to_file(&store.path);
         }
 
-        Command::List | Command::Ls => {
+        Command::Rename { old, new } => {
+            ensure_valid_list_name(&old);
+            ensure_valid_list_name(&new);
+
             // read in listfile
-            let list_file = ListFile::from_file(&todolists_path);
+            let mut list_file = ListFile::from_file(&store.path);
+
+            // rename list
+            match list_file.rename_list(&old, &new) {
+                Ok(_) => println!("Renamed todolist '{}' to '{}'", old, new),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1)
+                }
+            }
+
+            // write todolist file
+            list_file.to_file(&store.path);
+        }
+
+        Command::Clone {
+            source,
+            name,
+            reset,
+        } => {
+            ensure_valid_list_name(&source);
+            ensure_valid_list_name(&name);
+
+            // read in listfile
+            let mut list_file = ListFile::from_file(&store.path);
+
+            // copy list
+            match list_file.clone_list(&source, &name, reset) {
+                Ok(_) => println!("Created todolist '{}' from '{}'", name, source),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1)
+                }
+            }
+
+            // write todolist file
+            list_file.to_file(&store.path);
+        }
+
+        Command::Archive { name } => set_archived(&store, &name, true),
+        Command::Unarchive { name } => set_archived(&store, &name, false),
+
+        Command::List { archived } | Command::Ls { archived } => {
+            // read in listfile
+            let list_file = ListFile::from_file(&store.path);
 
             // confirm there is at least one list
             let mut names: Vec<&String> = list_file.get_list_names();
@@ -207,12 +913,19 @@ fn main() {
             }
 
             // retrieve focused list name
-            let focus = list_file.focused.as_ref().unwrap();
+            let focus = list_file.focused.as_ref();
+
+            // show which store the lists come from
+            println!("{}", store);
 
-            // print lists in alphabetical order
+            // print lists in alphabetical order, leaving out archived lists unless requested
             names.sort();
             for n in names {
-                if n == focus {
+                if list_file.lists[n].archived {
+                    if archived {
+                        println!("  {n} (archived)");
+                    }
+                } else if Some(n) == focus {
                     println!("* {n} *");
                 } else {
                     println!("  {n}");
@@ -223,17 +936,28 @@ fn main() {
         //
         // list commands
         //
-        Command::Tasks { all } | Command::Ts { all } => {
+        Command::Tasks {
+            all,
+            tags,
+            done_since,
+            sort,
+        }
+        | Command::Ts {
+            all,
+            tags,
+            done_since,
+            sort,
+        } => {
             // read in todolist file
-            let list_file = ListFile::from_file(&todolists_path);
+            let list_file = ListFile::from_file(&store.path);
 
             if list_file.num_lists() < 1 {
                 eprintln!("{}", NO_LISTS_MSG);
                 std::process::exit(1)
             }
 
-            // retrieve focused TodoList
-            let focused = match list_file.get_focused() {
+            // retrieve focused TodoList, or the one given with --list
+            let focused = match list_file.get_list(list_name) {
                 Ok(list) => list,
                 Err(e) => {
                     eprintln!("{}", e);
@@ -241,25 +965,47 @@ fn main() {
                 }
             };
 
+            // show which store the lists come from when viewing everything
+            if all {
+                println!("{}", store);
+            }
+
+            let filter = TaskFilter {
+                tags: tags.iter().map(|t| normalize_tag(t)).collect(),
+                done_since,
+            };
+
             // always print focused todolist
-            focused.print_tasks();
+            focused.print_tasks(sort, &filter);
 
-            // print rest of tasks if requested
+            // print rest of tasks if requested, skipping lists without matches when filtering
             if all {
                 for (name, list) in &list_file.lists {
-                    if *name != focused.name {
-                        list.print_tasks();
+                    if *name != focused.name
+                        && !list.archived
+                        && (!filter.is_active() || list.has_matching(&filter))
+                    {
+                        list.print_tasks(sort, &filter);
                     }
                 }
             }
         }
 
-        Command::Add { task, date } => {
+        Command::Add {
+            task,
+            date,
+            priority,
+            tags,
+            every,
+            from_completion,
+            parent,
+            after,
+        } => {
             // read in listfile
-            let mut list_file = ListFile::from_file(&todolists_path);
+            let mut list_file = ListFile::from_file(&store.path);
 
-            // retrieve focused TodoList
-            let list = match list_file.get_mut_focused() {
+            // retrieve TodoList to add to
+            let list = match list_file.get_mut_list(list_name) {
                 Ok(list) => list,
                 Err(e) => {
                     eprintln!("Error: {}", e);
@@ -268,18 +1014,48 @@ fn main() {
             };
 
             // add new task
-            list.add_tasks(task, date);
+            let tags = tags.iter().map(|t| normalize_tag(t)).collect();
+            let recurrence = every.map(|every| Recurrence {
+                every,
+                from: if from_completion {
+                    RecurFrom::Completion
+                } else {
+                    RecurFrom::Due
+                },
+            });
+            let added =
+                match list.add_tasks(task, date, priority, &tags, recurrence, parent.as_ref()) {
+                    Ok(added) => added,
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1)
+                    }
+                };
+
+            // record what the new tasks wait on
+            if !after.is_empty() {
+                for path in added {
+                    let changes = TaskChanges {
+                        after: Some(after.iter().cloned().collect()),
+                        ..Default::default()
+                    };
+                    if let Err(e) = list.edit_task(&path, changes) {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1)
+                    }
+                }
+            }
 
             // write todolist file
-            list_file.to_file(&todolists_path);
+            list_file.to_file(&store.path);
         }
 
-        Command::Drop { index } => {
+        Command::Next => {
             // read in listfile
-            let mut list_file = ListFile::from_file(&todolists_path);
+            let list_file = ListFile::from_file(&store.path);
 
-            // retrieve focused TodoList
-            let list = match list_file.get_mut_focused() {
+            // retrieve TodoList to look through
+            let list = match list_file.get_list(list_name) {
                 Ok(list) => list,
                 Err(e) => {
                     eprintln!("Error: {}", e);
@@ -287,19 +1063,76 @@ fn main() {
                 }
             };
 
-            // drop tasks
-            list.drop_tasks(index);
+            // widest path determines how IDs are spaced
+            let next = list.next_tasks();
+            let width = next
+                .iter()
+                .map(|(path, _)| path.to_string().len())
+                .max()
+                .unwrap_or(1);
 
-            // write todolist file
-            list_file.to_file(&todolists_path);
+            println!("-- {} --", list.name);
+            for (path, task) in next {
+                println!("{: <width$}| {}", path.to_string(), task);
+            }
+        }
+
+        Command::Show { id } => {
+            // read in listfile
+            let list_file = ListFile::from_file(&store.path);
+
+            // retrieve TodoList the task is in
+            let list = match list_file.get_list(id.list.as_deref().or(list_name)) {
+                Ok(list) => list,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1)
+                }
+            };
+
+            if let Err(e) = list.print_task(&id.path) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1)
+            }
         }
 
-        Command::Done { index } | Command::Do { index } => {
+        Command::Note { id, clear } => {
+            // take the notes from a pipe if there is one, otherwise edit the current notes
+            let notes = if clear {
+                String::new()
+            } else if !std::io::stdin().is_terminal() {
+                let mut notes = String::new();
+                if let Err(e) = std::io::stdin().read_to_string(&mut notes) {
+                    eprintln!("Error: failed to read notes from stdin: {}", e);
+                    std::process::exit(1)
+                }
+
+                // an empty pipe, e.g. from cron or </dev/null, must not wipe the notes by accident
+                if notes.trim().is_empty() {
+                    eprintln!(
+                        "Error: no notes were given on stdin, use --clear to remove the notes"
+                    );
+                    std::process::exit(1)
+                }
+                notes
+            } else {
+                let list_file = ListFile::from_file(&store.path);
+                let list = list_file.get_list(id.list.as_deref().or(list_name));
+                let current = match list.and_then(|list| list.get_task(&id.path)) {
+                    Ok(task) => task.notes.clone(),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1)
+                    }
+                };
+                unlocked(&store, &lock, &mut before, || edit_text(&current))
+            };
+
             // read in listfile
-            let mut list_file = ListFile::from_file(&todolists_path);
+            let mut list_file = ListFile::from_file(&store.path);
 
-            // retrieve focused TodoList
-            let list = match list_file.get_mut_focused() {
+            // retrieve TodoList the task is in
+            let list = match list_file.get_mut_list(id.list.as_deref().or(list_name)) {
                 Ok(list) => list,
                 Err(e) => {
                     eprintln!("Error: {}", e);
@@ -307,19 +1140,191 @@ fn main() {
                 }
             };
 
-            // mark tasks as done
-            list.update_completions(index, true);
+            match list.set_notes(&id.path, &notes) {
+                Ok(task) => println!("Updated task {}| {}", id, task),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1)
+                }
+            }
 
             // write todolist file
-            list_file.to_file(&todolists_path);
+            list_file.to_file(&store.path);
         }
 
-        Command::Undo { index } => {
+        Command::Drop { selection } => {
             // read in listfile
-            let mut list_file = ListFile::from_file(&todolists_path);
+            let mut list_file = ListFile::from_file(&store.path);
+
+            let groups = select_tasks(&list_file, selection.id, list_name, selection.position);
+            if selection.dry_run {
+                preview(&list_file, &groups, "drop");
+                return;
+            }
+
+            list_file.purge_trash(cli.trash_days);
+            for (list_name, paths) in groups {
+                // drop tasks, keeping them in the trash
+                let list = list_file.lists.get_mut(&list_name).unwrap();
+                let dropped = match list.drop_tasks(paths) {
+                    Ok(dropped) => dropped,
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1)
+                    }
+                };
+                let lines = dropped
+                    .iter()
+                    .map(|(path, task)| format!("Dropped {}| {}", path, task))
+                    .collect::<Vec<String>>();
+                let trash_ids = list_file.trash_tasks(&list_name, dropped);
+                for (line, trash_id) in lines.iter().zip(trash_ids) {
+                    println!("{} (trash ID {})", line, trash_id);
+                }
+            }
 
-            // retrieve focused TodoList
-            let list = match list_file.get_mut_focused() {
+            // write todolist file
+            list_file.to_file(&store.path);
+        }
+
+        Command::Done {
+            selection,
+            complete_parents,
+        }
+        | Command::Do {
+            selection,
+            complete_parents,
+        } => set_statuses(&store, list_name, selection, Status::Done, complete_parents),
+        Command::Undo { selection } => {
+            set_statuses(&store, list_name, selection, Status::Todo, false)
+        }
+        Command::Start { selection } => {
+            set_statuses(&store, list_name, selection, Status::InProgress, false)
+        }
+        Command::Wait { selection } => {
+            set_statuses(&store, list_name, selection, Status::Waiting, false)
+        }
+        Command::Block { selection } => {
+            set_statuses(&store, list_name, selection, Status::Blocked, false)
+        }
+        Command::Cancel { selection } => {
+            set_statuses(&store, list_name, selection, Status::Cancelled, false)
+        }
+
+        Command::Mv {
+            id,
+            to,
+            done,
+            dry_run,
+        } => {
+            ensure_valid_list_name(&to);
+            transfer_tasks(&store, list_name, id, &to, done, false, dry_run)
+        }
+        Command::Cp { id, to, dry_run } => {
+            ensure_valid_list_name(&to);
+            transfer_tasks(&store, list_name, id, &to, false, true, dry_run)
+        }
+
+        Command::History => {
+            let history = match History::load(&store.path) {
+                Ok(history) => history,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1)
+                }
+            };
+            if history.undo.is_empty() && history.redo.is_empty() {
+                println!("No commands to undo.");
+            }
+
+            // most recent first, starting with commands which were undone
+            for entry in &history.redo {
+                let time = entry.time.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+                println!("{}  {} (undone)", time, entry.command);
+            }
+            for entry in history.undo.iter().rev() {
+                let time = entry.time.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+                println!("{}  {}", time, entry.command);
+            }
+        }
+
+        Command::UndoLast => restore_history(&store, false),
+        Command::Redo => restore_history(&store, true),
+
+        Command::Trash { action } => {
+            // read in listfile
+            let mut list_file = ListFile::from_file(&store.path);
+            list_file.purge_trash(cli.trash_days);
+
+            match action {
+                Some(TrashAction::Empty) => {
+                    let count = list_file.empty_trash();
+                    println!("Permanently deleted {} item(s) from the trash", count);
+                }
+                None if list_file.trash.is_empty() => println!("The trash is empty."),
+                None => list_file.print_trash(),
+            }
+
+            // write todolist file
+            list_file.to_file(&store.path);
+        }
+
+        Command::Restore { id } => {
+            // read in listfile
+            let mut list_file = ListFile::from_file(&store.path);
+            list_file.purge_trash(cli.trash_days);
+
+            match list_file.restore(id) {
+                Ok(restored) => println!("Restored {}", restored),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1)
+                }
+            }
+
+            // write todolist file
+            list_file.to_file(&store.path);
+        }
+
+        Command::Edit {
+            id,
+            title,
+            date,
+            clear_date,
+            priority,
+            clear_priority,
+            after,
+            clear_after,
+            editor,
+        } => {
+            // edit the task as a document first, as the lists are re-read once the editor closes
+            let edited = if editor {
+                let list_file = ListFile::from_file(&store.path);
+                let list = list_file.get_list(id.list.as_deref().or(list_name));
+                let document = match list.and_then(|list| list.get_task(&id.path)) {
+                    Ok(task) => task_document(task),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1)
+                    }
+                };
+                let document = unlocked(&store, &lock, &mut before, || edit_text(&document));
+                match parse_task_document(&document) {
+                    Ok(changes) => Some(changes),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1)
+                    }
+                }
+            } else {
+                None
+            };
+
+            // read in listfile
+            let mut list_file = ListFile::from_file(&store.path);
+
+            // retrieve TodoList the task is in
+            let list = match list_file.get_mut_list(id.list.as_deref().or(list_name)) {
                 Ok(list) => list,
                 Err(e) => {
                     eprintln!("Error: {}", e);
@@ -327,11 +1332,58 @@ fn main() {
                 }
             };
 
-            // mark tasks as undone
-            list.update_completions(index, false);
+            // gather changes, either from the editor or from the given options
+            let changes = if let Some(changes) = edited {
+                changes
+            } else {
+                // new dependencies are added to the existing ones
+                let after = if clear_after {
+                    Some(Default::default())
+                } else if after.is_empty() {
+                    None
+                } else {
+                    let mut deps = match list.get_task(&id.path) {
+                        Ok(task) => task.after.clone(),
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1)
+                        }
+                    };
+                    deps.extend(after);
+                    Some(deps)
+                };
+                TaskChanges {
+                    title,
+                    date: if clear_date {
+                        Some(None)
+                    } else {
+                        date.map(Some)
+                    },
+                    priority: if clear_priority {
+                        Some(None)
+                    } else {
+                        priority.map(Some)
+                    },
+                    tags: None,
+                    after,
+                }
+            };
+
+            // apply changes
+            match list.edit_task(&id.path, changes) {
+                Ok(task) => println!("Updated task {}| {}", id, task),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1)
+                }
+            }
 
             // write todolist file
-            list_file.to_file(&todolists_path);
+            list_file.to_file(&store.path);
         }
     }
+
+    if let (true, Some(before)) = (journaled, before) {
+        record_history(&store, before, cli.history_depth);
+    }
 }
@@ -1,29 +1,129 @@
 use std::fmt::Display;
 
-use chrono::Datelike;
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, Local, Months, NaiveDate, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use self::errors::DateError;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq)]
+/// A calendar day with an optional time of day.
+/// Stored as an ISO `YYYY-MM-DD` string, or as an RFC 3339 timestamp when a time is given,
+/// which pins down the exact instant along with the UTC offset it was entered in.
+/// Dates entered without a year are resolved to a concrete year when parsed,
+/// so comparing and sorting dates never depends on when it happens.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct Date {
-    pub month: u8,
-    pub day: u8,
-    pub year: Option<u16>,
+    day: NaiveDate,
+    /// Due time in the offset it was entered in, always falling on `day`
+    time: Option<DateTime<FixedOffset>>,
 }
 
 impl Date {
-    pub fn new(month: u8, day: u8, year: Option<u16>) -> Self {
-        Self { month, day, year }
+    pub fn new(day: NaiveDate) -> Self {
+        Self { day, time: None }
+    }
+
+    /// Creates a date due at a specific instant.
+    pub fn at(time: DateTime<FixedOffset>) -> Self {
+        Self {
+            day: time.date_naive(),
+            time: Some(time),
+        }
+    }
+
+    /// Returns the calendar day of this date.
+    pub fn day(&self) -> NaiveDate {
+        self.day
+    }
+
+    /// Returns the due time, if any.
+    pub fn time(&self) -> Option<DateTime<FixedOffset>> {
+        self.time
+    }
+
+    /// Returns whether the date has passed: its due time if it has one, otherwise the whole day.
+    pub fn is_past(&self) -> bool {
+        match self.time {
+            Some(t) => t < Local::now(),
+            None => self.day < Local::now().date_naive(),
+        }
+    }
+
+    /// Returns a date on another day, keeping the time of day and offset of this one.
+    pub fn on_day(self, day: NaiveDate) -> Date {
+        match self.time {
+            Some(t) => Date::at(
+                t.offset()
+                    .from_local_datetime(&day.and_time(t.time()))
+                    .single()
+                    .unwrap(),
+            ),
+            None => Date::new(day),
+        }
+    }
+
+    /// Formats the date so that `parse_date` reads it back unchanged.
+    /// The offset is only written when it differs from the local one.
+    pub fn to_input_string(self) -> String {
+        match self.time {
+            Some(t) if t.offset() != t.with_timezone(&Local).offset() => {
+                format!("{} {}", self.day, t.format("%H:%M %:z"))
+            }
+            Some(t) => format!("{} {}", self.day, t.format("%H:%M")),
+            None => self.day.to_string(),
+        }
     }
 }
 
 impl Display for Date {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.year {
-            Some(y) => write!(f, "{:02}/{:02}/{}", self.month, self.day, y),
-            None => write!(f, "{:02}/{:02}", self.month, self.day),
+        // the year is only worth showing when it isn't the current one
+        if self.day.year() == Local::now().year() {
+            write!(f, "{}", self.day.format("%m/%d"))?;
+        } else {
+            write!(f, "{}", self.day.format("%m/%d/%Y"))?;
+        }
+
+        // show the offset only when it differs from the local one
+        if let Some(t) = self.time {
+            write!(f, " {}", t.format("%H:%M"))?;
+            if t.offset() != t.with_timezone(&Local).offset() {
+                write!(f, " {}", t.format("%:z"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        match date.time {
+            Some(t) => t.to_rfc3339(),
+            None => date.day.to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = DateError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let invalid = |e: chrono::ParseError| DateError::DateParseError {
+            given: s.clone(),
+            error: e.to_string(),
+        };
+        if s.contains('T') {
+            DateTime::parse_from_rfc3339(&s)
+                .map(Date::at)
+                .map_err(invalid)
+        } else {
+            NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+                .map(Date::new)
+                .map_err(invalid)
         }
     }
 }
@@ -32,16 +132,10 @@ impl Display for Date {
 
 impl PartialEq for Date {
     fn eq(&self, other: &Self) -> bool {
-        let curr_year = chrono::Utc::now().year() as u16;
-        let years_eq = match (self.year, other.year) {
-            (None, None) => true,
-            (Some(y1), Some(y2)) => y1 == y2,
-            (None, Some(y)) => y == curr_year,
-            (Some(y), None) => y == curr_year,
-        };
-        self.month == other.month && self.day == other.day && years_eq
+        self.cmp(other) == std::cmp::Ordering::Equal
     }
 }
+impl Eq for Date {}
 impl PartialOrd for Date {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -49,29 +143,16 @@ impl PartialOrd for Date {
 }
 impl Ord for Date {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        // goes through large to fine grained
-        // continues to next check if equal, otherwise returns ordering early
-        let curr_year = chrono::Utc::now().year() as u16;
-        match (self.year, other.year) {
-            (None, None) => {}
-            (None, Some(y)) => match curr_year.cmp(&y) {
-                core::cmp::Ordering::Equal => {}
-                ord => return ord,
-            },
-            (Some(y), None) => match y.cmp(&curr_year) {
-                core::cmp::Ordering::Equal => {}
-                ord => return ord,
-            },
-            (Some(y1), Some(y2)) => match y1.cmp(&y2) {
-                core::cmp::Ordering::Equal => {}
-                ord => return ord,
-            },
-        }
-        match self.month.cmp(&other.month) {
-            core::cmp::Ordering::Equal => {}
-            ord => return ord,
-        }
-        self.day.cmp(&other.day)
+        // timed dates by the instant they are due, which also orders them by local day,
+        // with dates lacking a time ahead of timed ones on the same local day
+        let key = |d: &Date| match d.time {
+            Some(t) => (
+                t.with_timezone(&Local).date_naive(),
+                Some(t.with_timezone(&Utc)),
+            ),
+            None => (d.day, None),
+        };
+        key(self).cmp(&key(other))
     }
 }
 
@@ -80,74 +161,407 @@ pub mod errors {
 
     #[derive(Error, Debug)]
     pub enum DateError {
-        /// Attempting to create a list with a used name
+        /// Given string is not a valid date
         #[error("Could not parse Date from: {given:?}. {error:?}")]
         DateParseError { given: String, error: String },
     }
 }
 
-/// Helper which parses a date from a string
+/// Helper which parses a date from a string.
+/// Accepts absolute dates (MM/DD[/YY[YY]] or ISO YYYY-MM-DD) as well as relative ones
+/// such as `today`, `fri`, `next monday`, `+3d` or `eom`, which are resolved against today.
+/// Dates without a year refer to their next occurrence, counting today.
+/// Any of these may be followed by a time of day in the local time zone, e.g. `fri 14:00` or
+/// `10/20 9am`; a time on its own means today. A UTC offset after the time, e.g.
+/// `11/09 23:30 +05:00`, reads the time in that offset instead.
 pub fn parse_date(s: &str) -> Result<Date, DateError> {
+    let s = s.trim();
+    let today = Local::now().date_naive();
+
+    // split off a trailing UTC offset, which may only follow a time
+    let (rest, offset) = match s.rsplit_once(char::is_whitespace) {
+        Some((rest, offset)) => match offset.parse::<FixedOffset>() {
+            Ok(offset) => (rest.trim(), Some(offset)),
+            Err(_) => (s, None),
+        },
+        None => (s, None),
+    };
+
+    // split off a trailing time of day
+    let (day, time) = match rest.rsplit_once(char::is_whitespace) {
+        Some((day, time)) => match parse_time(time) {
+            Some(time) => (day.trim(), Some(time)),
+            None => (rest, None),
+        },
+        None => match parse_time(rest) {
+            Some(time) => ("", Some(time)),
+            None => (rest, None),
+        },
+    };
+    if offset.is_some() && time.is_none() {
+        return Err(DateError::DateParseError {
+            given: s.to_string(),
+            error: "A UTC offset must follow a time of day, e.g. 14:00 +05:00".to_string(),
+        });
+    }
+    let day = if day.is_empty() {
+        today
+    } else {
+        parse_day(day, today)?
+    };
+
+    let time = match time {
+        Some(time) => time,
+        None => return Ok(Date::new(day)),
+    };
+    if let Some(offset) = offset {
+        let time = offset.from_local_datetime(&day.and_time(time)).unwrap();
+        return Ok(Date::at(time));
+    }
+
+    // interpret the time in the local time zone, picking the earlier time if clocks go back
+    match Local.from_local_datetime(&day.and_time(time)).earliest() {
+        Some(local) => Ok(Date::at(local.fixed_offset())),
+        None => Err(DateError::DateParseError {
+            given: s.to_string(),
+            error: "Invalid time. This time is skipped by a daylight saving change".to_string(),
+        }),
+    }
+}
+
+/// Helper which parses the start of a period in the past, e.g. for `--done-since`.
+/// Weekday names refer to the most recent such day (today included), and `yesterday` or offsets
+/// such as `-3d`, `-2w` or `-1m` count back from today. Anything else is read by `parse_date`.
+/// Days without a time start at midnight in the local time zone.
+pub fn parse_since(s: &str) -> Result<DateTime<FixedOffset>, DateError> {
+    let s = s.trim();
+    let today = Local::now().date_naive();
+
+    let date = match parse_past_date(&s.to_lowercase(), today) {
+        Some(day) => Date::new(day),
+        None => parse_date(s)?,
+    };
+    if let Some(time) = date.time() {
+        return Ok(time);
+    }
+    match Local
+        .from_local_datetime(&date.day().and_time(NaiveTime::MIN))
+        .earliest()
+    {
+        Some(midnight) => Ok(midnight.fixed_offset()),
+        None => Err(DateError::DateParseError {
+            given: s.to_string(),
+            error: "Invalid day. Midnight is skipped by a daylight saving change".to_string(),
+        }),
+    }
+}
+
+/// Resolves a description of a day in the past against `today`.
+/// ### Returns
+/// The resolved day, or None if the string is not a past date
+fn parse_past_date(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if s == "yesterday" {
+        return today.pred_opt();
+    }
+
+    // offsets such as -3d, -2w or -1m
+    let offset_regex = Regex::new(r"^-(\d{1,4})([dwm])$").unwrap();
+    if let Some(caps) = offset_regex.captures(s) {
+        let n = caps[1].parse::<u32>().ok()?;
+        return match &caps[2] {
+            "d" => today.checked_sub_days(Days::new(n as u64)),
+            "w" => today.checked_sub_days(Days::new(n as u64 * 7)),
+            _ => today.checked_sub_months(Months::new(n)),
+        };
+    }
+
+    // weekday names, counting back to the latest such day
+    let weekday = s.parse::<Weekday>().ok()?;
+    let days_since =
+        (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    today.checked_sub_days(Days::new(days_since as u64))
+}
+
+/// Parses a time of day such as `14:00`, `9am` or `9:30pm`.
+/// ### Returns
+/// The time, or None if the string is not a time of day
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    let regex = Regex::new(r"^(\d{1,2})(?::(\d{2}))?(am|pm)?$").unwrap();
+    let s = s.to_lowercase();
+    let caps = regex.captures(&s)?;
+
+    // a bare number is a day or an offset, not a time
+    let minutes = caps.get(2).map(|m| m.as_str().parse::<u32>().unwrap());
+    let meridiem = caps.get(3).map(|m| m.as_str());
+    if minutes.is_none() && meridiem.is_none() {
+        return None;
+    }
+
+    let hour = caps[1].parse::<u32>().unwrap();
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some("am") => hour % 12,
+        Some(_) => hour % 12 + 12,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minutes.unwrap_or(0), 0)
+}
+
+/// Parses the calendar day part of a date.
+fn parse_day(s: &str, today: NaiveDate) -> Result<NaiveDate, DateError> {
+    // relative dates always resolve to a concrete day
+    if let Some(date) = parse_relative_date(&s.to_lowercase(), today) {
+        return Ok(date);
+    }
+
     // regex which matches dates of the form MM/DD, MM/DD/YY, MM/DD/YYYY
     let regex = Regex::new(r"^(\d{1,2})(?:\/|-)(\d{1,2})(?:(?:\/|-)(\d{4}|\d{2}))?$").unwrap();
+    // regex which matches ISO dates of the form YYYY-MM-DD
+    let iso_regex = Regex::new(r"^(\d{4})-(\d{1,2})-(\d{1,2})$").unwrap();
+
+    // extract captures from whichever format matches
+    let (month, day, year) = if let Some(caps) = iso_regex.captures(s) {
+        (
+            caps[2].parse::<u32>().unwrap(),
+            caps[3].parse::<u32>().unwrap(),
+            Some(caps[1].parse::<i32>().unwrap()),
+        )
+    } else if let Some(caps) = regex.captures(s) {
+        let year = caps.get(3).map(|m| {
+            let y = m.as_str().parse::<i32>().unwrap();
+            match y {
+                0..=99 => 2000 + y, // assume 2000s if only 2-digit year
+                _ => y,
+            }
+        });
+        (
+            caps[1].parse::<u32>().unwrap(),
+            caps[2].parse::<u32>().unwrap(),
+            year,
+        )
+    } else {
+        return Err(DateError::DateParseError { given: s.to_string(), error: "Invalid date format. Accepted formats: MM/DD, MM/DD/YY, MM/DD/YYYY, YYYY-MM-DD (note single digit days and months are also accepted), today, tomorrow, weekdays (fri, next monday), offsets (+3d, +2w, +1m), eow, eom".to_string() });
+    };
+
+    let invalid = |error: String| DateError::DateParseError {
+        given: s.to_string(),
+        error,
+    };
+
+    // validate month and day against the calendar
+    if !(1..=12).contains(&month) {
+        return Err(invalid(
+            "Invalid date. Month must be between 1 and 12".to_string(),
+        ));
+    }
+    if day == 0 {
+        return Err(invalid("Invalid date. Day must be at least 1".to_string()));
+    }
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day).ok_or_else(|| {
+            invalid(format!(
+                "Invalid date. {} has only {} days",
+                month_name(month, Some(year)),
+                days_in_month(month, year)
+            ))
+        }),
+        None => next_occurrence(month, day, today).ok_or_else(|| {
+            invalid(format!(
+                "Invalid date. {} has at most {} days",
+                month_name(month, None),
+                days_in_month(month, 2000) // a leap year, so February allows the 29th
+            ))
+        }),
+    }
+}
+
+/// Finds the first day on or after `today` falling on the given month and day.
+/// February 29th resolves to the next leap year.
+fn next_occurrence(month: u32, day: u32, today: NaiveDate) -> Option<NaiveDate> {
+    // leap days can be up to eight years apart
+    (today.year()..=today.year() + 8)
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .find(|date| *date >= today)
+}
 
-    // check if valid format
-    if !regex.is_match(s) {
-        return Err(DateError::DateParseError { given: s.to_string(), error: "Invalid date format. Accepted formats: MM/DD, MM/DD/YY, MM/DD/YYYY  (note single digit days and months are also accepted)".to_string() });
+/// Number of days in the given month of the given year.
+fn days_in_month(month: u32, year: i32) -> u32 {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let next = first.checked_add_months(Months::new(1)).unwrap();
+    (next - first).num_days() as u32
+}
+
+/// Human readable month name, including the year if it matters for the month's length.
+fn month_name(month: u32, year: Option<i32>) -> String {
+    let first = NaiveDate::from_ymd_opt(year.unwrap_or(2000), month, 1).unwrap();
+    match year {
+        Some(_) if month == 2 => first.format("%B %Y").to_string(),
+        _ => first.format("%B").to_string(),
     }
+}
 
-    // extract captures
-    let caps = regex.captures(s).unwrap();
-    let month = caps.get(1).unwrap().as_str().parse::<u8>().unwrap();
-    let day = caps.get(2).unwrap().as_str().parse::<u8>().unwrap();
-    let year = caps.get(3).map(|m| {
-        let y = m.as_str().parse::<u16>().unwrap();
-        match y {
-            0..=99 => 2000 + y, // assume 2000s if only 2-digit year
-            _ => y,
+/// Resolves a relative date description against `today`.
+/// Weekday names refer to the next such day after today, while `next <weekday>` refers to
+/// that day in the following week (weeks start on Monday).
+/// ### Returns
+/// The resolved day, or None if the string is not a relative date
+fn parse_relative_date(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match s {
+        "today" => return Some(today),
+        "tomorrow" | "tmr" => return today.succ_opt(),
+        // end of week is the upcoming Sunday
+        "eow" => {
+            let days_left = 6 - today.weekday().num_days_from_monday();
+            return today.checked_add_days(Days::new(days_left as u64));
         }
-    });
-
-    // validate month-day combos
-    match month {
-        1 | 3 | 5 | 7 | 8 | 10 | 12 => {
-            if day > 31 {
-                return Err(DateError::DateParseError {
-                    given: s.to_string(),
-                    error: "Invalid date. This month has at most 31 days".to_string(),
-                });
-            }
+        // end of month is the day before the first of next month
+        "eom" => {
+            let first = today.with_day(1)?;
+            return first.checked_add_months(Months::new(1))?.pred_opt();
         }
-        4 | 6 | 9 | 11 => {
-            if day > 30 {
-                return Err(DateError::DateParseError {
-                    given: s.to_string(),
-                    error: "Invalid date. This month has at most 30 days".to_string(),
-                });
-            }
+        _ => {}
+    }
+
+    // offsets such as +3d, +2w or +1m
+    let offset_regex = Regex::new(r"^\+(\d{1,4})([dwm])$").unwrap();
+    if let Some(caps) = offset_regex.captures(s) {
+        let n = caps[1].parse::<u32>().ok()?;
+        return match &caps[2] {
+            "d" => today.checked_add_days(Days::new(n as u64)),
+            "w" => today.checked_add_days(Days::new(n as u64 * 7)),
+            _ => today.checked_add_months(Months::new(n)),
+        };
+    }
+
+    // weekday names, optionally preceded by `next`
+    let (next_week, name) = match s.strip_prefix("next ") {
+        Some(name) => (true, name.trim()),
+        None => (false, s),
+    };
+    let weekday = name.parse::<Weekday>().ok()?;
+    if next_week {
+        let next_monday =
+            today.checked_add_days(Days::new(7 - today.weekday().num_days_from_monday() as u64))?;
+        next_monday.checked_add_days(Days::new(weekday.num_days_from_monday() as u64))
+    } else {
+        let days_until =
+            (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        let days_until = if days_until == 0 { 7 } else { days_until };
+        today.checked_add_days(Days::new(days_until as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saturday, so weekdays on either side of it can be checked.
+    fn today() -> NaiveDate {
+        ymd(2026, 10, 17)
+    }
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn resolves_dates_without_a_year_to_their_next_occurrence() {
+        assert_eq!(parse_day("10/20", today()).unwrap(), ymd(2026, 10, 20));
+        assert_eq!(parse_day("10/17", today()).unwrap(), ymd(2026, 10, 17));
+        assert_eq!(parse_day("3/5", today()).unwrap(), ymd(2027, 3, 5));
+        assert_eq!(parse_day("03-05", today()).unwrap(), ymd(2027, 3, 5));
+    }
+
+    #[test]
+    fn keeps_given_years() {
+        assert_eq!(parse_day("3/5/27", today()).unwrap(), ymd(2027, 3, 5));
+        assert_eq!(parse_day("3/5/2001", today()).unwrap(), ymd(2001, 3, 5));
+        assert_eq!(parse_day("2024-05-01", today()).unwrap(), ymd(2024, 5, 1));
+    }
+
+    #[test]
+    fn resolves_relative_dates() {
+        let day = |s| parse_day(s, today()).unwrap();
+        assert_eq!(day("today"), ymd(2026, 10, 17));
+        assert_eq!(day("Tomorrow"), ymd(2026, 10, 18));
+        assert_eq!(day("fri"), ymd(2026, 10, 23));
+        assert_eq!(day("sat"), ymd(2026, 10, 24));
+        assert_eq!(day("next monday"), ymd(2026, 10, 19));
+        assert_eq!(day("+3d"), ymd(2026, 10, 20));
+        assert_eq!(day("+2w"), ymd(2026, 10, 31));
+        assert_eq!(day("+1m"), ymd(2026, 11, 17));
+        assert_eq!(day("eow"), ymd(2026, 10, 18));
+        assert_eq!(day("eom"), ymd(2026, 10, 31));
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        for s in ["", "someday", "10/20/2026/1", "+3y", "next"] {
+            assert!(parse_day(s, today()).is_err(), "{s:?} should not parse");
         }
-        2 => {
-            if year.is_none() {
-                if day > 28 {
-                    return Err(DateError::DateParseError {
-                        given: s.to_string(),
-                        error: "Invalid date. February has at most 28 days".to_string(),
-                    });
-                }
-            } else if day > 29 {
-                return Err(DateError::DateParseError {
-                    given: s.to_string(),
-                    error: "Invalid date. February has at most 29 days".to_string(),
-                });
-            }
+    }
+
+    #[test]
+    fn resolves_leap_days_to_the_next_leap_year() {
+        assert_eq!(parse_day("2/29", today()).unwrap(), ymd(2028, 2, 29));
+        assert_eq!(parse_day("2/29/2028", today()).unwrap(), ymd(2028, 2, 29));
+    }
+
+    #[test]
+    fn rejects_days_missing_from_the_calendar() {
+        for s in [
+            "2/29/2027",
+            "2/30",
+            "4/31/2026",
+            "13/01",
+            "0/10",
+            "10/0",
+            "2026-02-29",
+        ] {
+            assert!(parse_day(s, today()).is_err(), "{s:?} should not parse");
         }
-        _ => {
-            return Err(DateError::DateParseError {
-                given: s.to_string(),
-                error: "Invalid date. Month must be between 1 and 12".to_string(),
-            })
+    }
+
+    #[test]
+    fn always_gives_a_concrete_day() {
+        let today = Local::now().date_naive();
+        let date = parse_date("12/31").unwrap();
+        assert!(date.day() >= today);
+        assert_eq!(date.time(), None);
+        assert_eq!(parse_date("today").unwrap().day(), today);
+    }
+
+    #[test]
+    fn reads_times_in_the_given_offset() {
+        let date = parse_date("2026-11-09 23:30 +05:00").unwrap();
+        let time = date.time().unwrap();
+        assert_eq!(date.day(), ymd(2026, 11, 9));
+        assert_eq!(time.offset().local_minus_utc(), 5 * 3600);
+        assert_eq!(time.to_rfc3339(), "2026-11-09T23:30:00+05:00");
+
+        // an offset only makes sense after a time of day
+        assert!(parse_date("2026-11-09 +05:00").is_err());
+    }
+
+    #[test]
+    fn input_strings_read_back_unchanged() {
+        for s in [
+            "2026-11-09",
+            "2026-11-09T23:30:00+05:00",
+            "2026-11-21T01:00:00-07:00",
+        ] {
+            let date = Date::try_from(s.to_string()).unwrap();
+            let reread = parse_date(&date.to_input_string()).unwrap();
+            assert_eq!(String::from(reread), s);
         }
     }
 
-    Ok(Date::new(month, day, year))
+    #[test]
+    fn sorts_timed_dates_by_instant() {
+        let date = |s: &str| Date::try_from(s.to_string()).unwrap();
+
+        // 06:00 UTC on the 21st is later than 01:00 UTC, despite being entered on the 20th
+        assert!(date("2026-11-20T23:00:00-07:00") > date("2026-11-21T01:00:00+00:00"));
+        assert!(date("2026-11-21T01:00:00+05:00") < date("2026-11-20T23:00:00+00:00"));
+    }
 }
@@ -0,0 +1,190 @@
+use std::{
+    collections::BTreeSet,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::ValueEnum;
+
+use self::errors::EditorError;
+use crate::utils::{
+    date::parse_date,
+    priority::Priority,
+    tags::normalize_tag,
+    task_ref::parse_task_path,
+    todolist::{Task, TaskChanges},
+};
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` is set.
+const DEFAULT_EDITOR: &str = "vi";
+
+pub mod errors {
+    use thiserror::Error;
+
+    #[derive(Error, Debug)]
+    pub enum EditorError {
+        /// Temporary file for editing could not be written or read back
+        #[error("Failed to prepare file for editing: {error}")]
+        TempFile { error: String },
+        /// Editor process could not be started
+        #[error("Failed to launch editor {editor:?}: {error}")]
+        Launch { editor: String, error: String },
+        /// Editor exited unsuccessfully, so its changes are discarded
+        #[error("Editor {editor:?} exited with an error, no changes made")]
+        Aborted { editor: String },
+        /// Edited document could not be understood
+        #[error("Could not parse edited task: {error}")]
+        InvalidDocument { error: String },
+    }
+}
+
+/// Opens the given text in the user's editor and returns the edited text.
+/// ### Returns
+/// Edited text or EditorError
+pub fn edit_text(initial: &str) -> Result<String, EditorError> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| DEFAULT_EDITOR.to_string());
+
+    // write the document to a temporary file for the editor to open
+    let temp_file_error = |e: io::Error| EditorError::TempFile {
+        error: e.to_string(),
+    };
+    let (path, mut file) = create_temp_file().map_err(temp_file_error)?;
+    let written = file.write_all(initial.as_bytes());
+    drop(file);
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&path);
+        return Err(temp_file_error(e));
+    }
+
+    // editors are commonly configured with arguments, e.g. `code --wait`
+    let mut parts = editor.split_whitespace();
+    let status = Command::new(parts.next().unwrap_or(DEFAULT_EDITOR))
+        .args(parts)
+        .arg(&path)
+        .status();
+
+    let result = match status {
+        Ok(status) if status.success() => std::fs::read_to_string(&path).map_err(temp_file_error),
+        Ok(_) => Err(EditorError::Aborted { editor }),
+        Err(e) => Err(EditorError::Launch {
+            editor,
+            error: e.to_string(),
+        }),
+    };
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Creates a new temporary file which only the current user can read, with a name no other
+/// process can claim first. Existing files and symlinks at the chosen path are never opened.
+/// ### Returns
+/// Path of the file and the file opened for writing, or the io::Error from creating it
+fn create_temp_file() -> io::Result<(PathBuf, File)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    let mut attempt = 0;
+    loop {
+        let name = format!(
+            "todo-edit-{}-{:08x}.txt",
+            std::process::id(),
+            nanos.wrapping_add(attempt)
+        );
+        let path = std::env::temp_dir().join(name);
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            // another file already has this name, so try the next one
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Renders a task as a small text document for editing.
+pub fn task_document(task: &Task) -> String {
+    format!(
+        "# Edit the task below, lines starting with '#' are ignored.\n\
+         # Leave the date or priority empty to remove it. Priority is one of high, medium, low.\n\
+         # Tags are separated by spaces, as are the IDs of tasks this one waits on.\n\
+         title: {}\n\
+         date: {}\n\
+         priority: {}\n\
+         tags: {}\n\
+         after: {}\n",
+        task.title,
+        // write the full date so it parses back to the same day
+        task.date.map(|d| d.to_input_string()).unwrap_or_default(),
+        task.priority
+            .and_then(|p| p.to_possible_value())
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default(),
+        task.tags.iter().cloned().collect::<Vec<_>>().join(" "),
+        task.after
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    )
+}
+
+/// Parses a document produced by `task_document` back into changes for the task.
+/// ### Returns
+/// TaskChanges or EditorError
+pub fn parse_task_document(document: &str) -> Result<TaskChanges, EditorError> {
+    let invalid = |error: String| EditorError::InvalidDocument { error };
+
+    let mut changes = TaskChanges {
+        date: Some(None),
+        priority: Some(None),
+        tags: Some(BTreeSet::new()),
+        after: Some(BTreeSet::new()),
+        ..Default::default()
+    };
+    for line in document.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => return Err(invalid(format!("unexpected line {:?}", line))),
+        };
+        match key {
+            "title" => changes.title = Some(value.to_string()),
+            "date" if value.is_empty() => changes.date = Some(None),
+            "date" => {
+                let date = parse_date(value).map_err(|e| invalid(e.to_string()))?;
+                changes.date = Some(Some(date));
+            }
+            "priority" if value.is_empty() => changes.priority = Some(None),
+            "priority" => {
+                let priority = Priority::from_str(value, true).map_err(invalid)?;
+                changes.priority = Some(Some(priority));
+            }
+            "tags" => changes.tags = Some(value.split_whitespace().map(normalize_tag).collect()),
+            "after" => {
+                let after = value
+                    .split_whitespace()
+                    .map(parse_task_path)
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| invalid(e.to_string()))?;
+                changes.after = Some(after);
+            }
+            _ => return Err(invalid(format!("unexpected line {:?}", line))),
+        }
+    }
+
+    match changes.title {
+        Some(ref title) if !title.is_empty() => Ok(changes),
+        _ => Err(invalid("title must not be empty".to_string())),
+    }
+}
@@ -0,0 +1,231 @@
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use self::errors::HistoryError;
+
+/// Number of commands kept in the history when no depth is configured.
+pub const DEFAULT_HISTORY_DEPTH: usize = 50;
+
+/// Journal of changes made to a ListFile, kept in a sibling `.history` file.
+/// Each entry holds a snapshot of the ListFile from before a command changed it,
+/// so undoing a command is a matter of restoring its snapshot.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    /// Commands which can be undone, oldest first
+    pub undo: Vec<Entry>,
+    /// Commands which were undone and can be redone, most recently undone last
+    pub redo: Vec<Entry>,
+}
+
+/// A command which changed the ListFile.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    /// Command line as it was entered, e.g. `todo drop 1 2`
+    pub command: String,
+    pub time: DateTime<FixedOffset>,
+    /// Raw ListFile on the other side of the command: from before it for undo entries,
+    /// and from after it for redo entries
+    pub snapshot: Value,
+}
+
+pub mod errors {
+    use thiserror::Error;
+
+    #[derive(Error, Debug)]
+    pub enum HistoryError {
+        /// History file exists but could not be read or deserialized
+        #[error("Failed to read history {path:?}: {error}")]
+        Unreadable { path: String, error: String },
+        /// History file could not be written
+        #[error("Failed to write history {path:?}: {error}")]
+        WriteFailed { path: String, error: String },
+        /// No commands have been recorded, or all of them have been undone
+        #[error("Nothing to undo.")]
+        NothingToUndo,
+        /// No commands have been undone since the last change
+        #[error("Nothing to redo.")]
+        NothingToRedo,
+    }
+}
+
+impl History {
+    /// Path of the history kept alongside the given ListFile.
+    fn path(file_path: &str) -> String {
+        format!("{}.history", file_path)
+    }
+
+    /// Reads the history of the given ListFile. A missing history is empty.
+    /// ### Returns
+    /// History or HistoryError
+    pub fn load(file_path: &str) -> Result<Self, HistoryError> {
+        let path = Self::path(file_path);
+        let unreadable = |error: String| HistoryError::Unreadable {
+            path: path.clone(),
+            error,
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| unreadable(e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(unreadable(e.to_string())),
+        }
+    }
+
+    /// Writes the history of the given ListFile, replacing the old one in a single rename.
+    /// ### Returns
+    /// Result indicating success of the write
+    pub fn save(&self, file_path: &str) -> Result<(), HistoryError> {
+        let path = Self::path(file_path);
+        let failed = |error: String| HistoryError::WriteFailed {
+            path: path.clone(),
+            error,
+        };
+
+        let encoded = serde_json::to_string(&self).map_err(|e| failed(e.to_string()))?;
+        let tmp_path = format!("{}.tmp", path);
+        std::fs::write(&tmp_path, encoded).map_err(|e| failed(e.to_string()))?;
+        std::fs::rename(&tmp_path, &path).map_err(|e| failed(e.to_string()))
+    }
+
+    /// Records a command which changed the ListFile from `before`.
+    /// Anything previously undone can no longer be redone, and only the latest `depth`
+    /// commands are kept.
+    pub fn record(&mut self, command: String, before: Value, depth: usize) {
+        self.undo.push(Entry {
+            command,
+            time: Local::now().fixed_offset(),
+            snapshot: before,
+        });
+        self.redo.clear();
+
+        let excess = self.undo.len().saturating_sub(depth);
+        self.undo.drain(..excess);
+    }
+
+    /// Steps back over the latest command, given the raw ListFile as it is now.
+    /// ### Returns
+    /// The undone command and the snapshot to restore, or HistoryError
+    pub fn undo(&mut self, current: Value) -> Result<(String, Value), HistoryError> {
+        let entry = self.undo.pop().ok_or(HistoryError::NothingToUndo)?;
+        self.redo.push(Entry {
+            command: entry.command.clone(),
+            time: entry.time,
+            snapshot: current,
+        });
+        Ok((entry.command, entry.snapshot))
+    }
+
+    /// Re-applies the most recently undone command, given the raw ListFile as it is now.
+    /// ### Returns
+    /// The redone command and the snapshot to restore, or HistoryError
+    pub fn redo(&mut self, current: Value) -> Result<(String, Value), HistoryError> {
+        let entry = self.redo.pop().ok_or(HistoryError::NothingToRedo)?;
+        self.undo.push(Entry {
+            command: entry.command.clone(),
+            time: entry.time,
+            snapshot: current,
+        });
+        Ok((entry.command, entry.snapshot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// History of `todo add a` then `todo add b`, starting from an empty file.
+    fn history() -> History {
+        let mut history = History::default();
+        history.record("todo add a".to_string(), json!([]), DEFAULT_HISTORY_DEPTH);
+        history.record(
+            "todo add b".to_string(),
+            json!(["a"]),
+            DEFAULT_HISTORY_DEPTH,
+        );
+        history
+    }
+
+    fn commands(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|e| e.command.as_str()).collect()
+    }
+
+    #[test]
+    fn undo_and_redo_swap_snapshots() {
+        let mut history = history();
+
+        let (command, restored) = history.undo(json!(["a", "b"])).unwrap();
+        assert_eq!((command.as_str(), &restored), ("todo add b", &json!(["a"])));
+        let (command, restored) = history.undo(restored).unwrap();
+        assert_eq!((command.as_str(), &restored), ("todo add a", &json!([])));
+        assert!(matches!(
+            history.undo(restored.clone()),
+            Err(HistoryError::NothingToUndo)
+        ));
+        assert_eq!(commands(&history.redo), ["todo add b", "todo add a"]);
+
+        let (command, restored) = history.redo(restored).unwrap();
+        assert_eq!((command.as_str(), &restored), ("todo add a", &json!(["a"])));
+        let (command, restored) = history.redo(restored).unwrap();
+        assert_eq!(
+            (command.as_str(), &restored),
+            ("todo add b", &json!(["a", "b"]))
+        );
+        assert!(matches!(
+            history.redo(restored),
+            Err(HistoryError::NothingToRedo)
+        ));
+        assert_eq!(commands(&history.undo), ["todo add a", "todo add b"]);
+    }
+
+    #[test]
+    fn recording_clears_what_can_be_redone() {
+        let mut history = history();
+        history.undo(json!(["a", "b"])).unwrap();
+        assert_eq!(history.redo.len(), 1);
+
+        history.record(
+            "todo add c".to_string(),
+            json!(["a"]),
+            DEFAULT_HISTORY_DEPTH,
+        );
+        assert!(history.redo.is_empty());
+        assert!(matches!(
+            history.redo(json!(["a", "c"])),
+            Err(HistoryError::NothingToRedo)
+        ));
+        assert_eq!(commands(&history.undo), ["todo add a", "todo add c"]);
+    }
+
+    #[test]
+    fn keeps_only_the_latest_commands() {
+        let mut history = History::default();
+        for n in 0..5 {
+            history.record(format!("todo add {}", n), json!(n), 3);
+        }
+        assert_eq!(
+            commands(&history.undo),
+            ["todo add 2", "todo add 3", "todo add 4"]
+        );
+        assert_eq!(history.undo[0].snapshot, json!(2));
+
+        // a smaller depth trims older commands on the next record
+        history.record("todo add 5".to_string(), json!(5), 1);
+        assert_eq!(commands(&history.undo), ["todo add 5"]);
+    }
+
+    #[test]
+    fn depth_of_zero_keeps_no_history() {
+        let mut history = history();
+        history.undo(json!(["a", "b"])).unwrap();
+
+        history.record("todo add c".to_string(), json!(["a"]), 0);
+        assert!(history.undo.is_empty() && history.redo.is_empty());
+        assert!(matches!(
+            history.undo(json!(["a", "c"])),
+            Err(HistoryError::NothingToUndo)
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::utils::todolist::errors::ListError;
+
+/// How long to wait for another `todo` process to release the ListFile.
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the lock is retried while waiting.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Advisory lock on a sibling `.lock` file of the ListFile.
+/// Held for the whole read-modify-write cycle of a command so concurrent `todo` processes
+/// cannot overwrite each other's changes. The lock is released when this value is dropped.
+#[derive(Debug)]
+pub struct ListFileLock {
+    file: File,
+    path: String,
+}
+
+impl ListFileLock {
+    /// Acquires an exclusive lock for the given ListFile, waiting up to `timeout`.
+    /// ### Returns
+    /// ListFileLock guard or ListError if the lock could not be taken in time
+    pub fn acquire(file_path: &str, timeout: Duration) -> Result<Self, ListError> {
+        let lock_path = format!("{}.lock", file_path);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| ListError::LockFailed {
+                path: lock_path.clone(),
+                error: e.to_string(),
+            })?;
+
+        let lock = ListFileLock {
+            file,
+            path: lock_path,
+        };
+        lock.wait(timeout)?;
+        Ok(lock)
+    }
+
+    /// Lets other `todo` processes use the ListFile while `f` runs, such as while an editor is
+    /// open, then takes the lock back. Anything read before `f` ran may be stale afterwards.
+    /// ### Returns
+    /// Result of `f`, or ListError if the lock could not be taken back in time
+    pub fn unlocked<T>(&self, timeout: Duration, f: impl FnOnce() -> T) -> Result<T, ListError> {
+        self.file.unlock().map_err(|e| ListError::LockFailed {
+            path: self.path.clone(),
+            error: e.to_string(),
+        })?;
+        let result = f();
+        self.wait(timeout)?;
+        Ok(result)
+    }
+
+    /// Retries the lock until it is free or the timeout elapses.
+    fn wait(&self, timeout: Duration) -> Result<(), ListError> {
+        let start = Instant::now();
+        loop {
+            match self.file.try_lock() {
+                Ok(()) => return Ok(()),
+                Err(TryLockError::WouldBlock) if start.elapsed() < timeout => {
+                    thread::sleep(LOCK_RETRY_INTERVAL)
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(ListError::LockTimeout {
+                        path: self.path.clone(),
+                        seconds: timeout.as_secs(),
+                    })
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(ListError::LockFailed {
+                        path: self.path.clone(),
+                        error: e.to_string(),
+                    })
+                }
+            }
+        }
+    }
+}
@@ -1,2 +1,14 @@
 pub mod date;
+pub mod editor;
+pub mod history;
+pub mod lock;
+pub mod paths;
+pub mod priority;
+pub mod recur;
+pub mod schema;
+pub mod selector;
+pub mod status;
+pub mod tags;
+pub mod task_ref;
 pub mod todolist;
+pub mod trash;
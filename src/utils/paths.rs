@@ -0,0 +1,168 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use self::errors::PathError;
+
+/// Name of the ListFile used before XDG data directories were supported.
+const LEGACY_FILE_NAME: &str = ".todolists";
+/// Location of the ListFile relative to the XDG data directory.
+const XDG_FILE_PATH: &str = "todo/todolists.json";
+/// Name of a per-project ListFile, discovered by walking up from the working directory.
+pub const PROJECT_FILE_NAME: &str = ".todo";
+
+/// Where the active ListFile was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreKind {
+    /// Given explicitly with `--file` or `TODO_FILE`
+    Explicit,
+    /// Per-project `.todo` file in the working directory or one of its parents
+    Project,
+    /// Global ListFile in the user's data directory
+    Global,
+}
+
+/// The ListFile a command operates on.
+#[derive(Debug, Clone)]
+pub struct Store {
+    pub path: String,
+    pub kind: StoreKind,
+}
+
+impl Display for Store {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            StoreKind::Explicit => "file",
+            StoreKind::Project => "project",
+            StoreKind::Global => "global",
+        };
+        write!(f, "[{}] {}", kind, self.path)
+    }
+}
+
+pub mod errors {
+    use thiserror::Error;
+
+    #[derive(Error, Debug)]
+    pub enum PathError {
+        /// Home directory could not be determined
+        #[error("Could not locate your home directory")]
+        NoHomeDir,
+        /// Data directory for the ListFile could not be created
+        #[error("Could not create directory {path:?}: {error}")]
+        CreateDirFailed { path: String, error: String },
+        /// Working directory could not be determined
+        #[error("Could not determine the current directory: {error}")]
+        NoCurrentDir { error: String },
+        /// Attempting to initialize a store where one already exists
+        #[error("Cannot initialize todolists, {path:?} already exists")]
+        StoreExists { path: String },
+        /// Legacy ListFile could not be moved to its new location
+        #[error("Could not move {from:?} to {to:?}: {error}")]
+        MigrationFailed {
+            from: String,
+            to: String,
+            error: String,
+        },
+    }
+}
+
+/// Determines which ListFile to use.
+/// An explicitly given path (from `--file` or `TODO_FILE`) always wins, followed by a `.todo` file
+/// in the working directory or its closest parent. Otherwise the global ListFile in
+/// `$XDG_DATA_HOME/todo/` is used, moving a legacy `~/.todolists` there the first time.
+/// ### Returns
+/// Store describing the ListFile or PathError
+pub fn resolve_store(file: Option<PathBuf>) -> Result<Store, PathError> {
+    if let Some(file) = file {
+        return Ok(Store {
+            path: file.display().to_string(),
+            kind: StoreKind::Explicit,
+        });
+    }
+
+    if let Some(path) = find_project_file(&current_dir()?) {
+        return Ok(Store {
+            path: path.display().to_string(),
+            kind: StoreKind::Project,
+        });
+    }
+
+    Ok(Store {
+        path: global_list_file()?.display().to_string(),
+        kind: StoreKind::Global,
+    })
+}
+
+/// Path at which `todo init` creates a per-project ListFile.
+pub fn project_file_path() -> Result<PathBuf, PathError> {
+    Ok(current_dir()?.join(PROJECT_FILE_NAME))
+}
+
+fn current_dir() -> Result<PathBuf, PathError> {
+    std::env::current_dir().map_err(|e| PathError::NoCurrentDir {
+        error: e.to_string(),
+    })
+}
+
+/// Walks up from `start` looking for a per-project ListFile, the way git finds `.git`.
+fn find_project_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Locates the global ListFile, creating its directory and migrating a legacy file if needed.
+fn global_list_file() -> Result<PathBuf, PathError> {
+    let home = match home::home_dir() {
+        Some(home) if !home.as_os_str().is_empty() => home,
+        _ => return Err(PathError::NoHomeDir),
+    };
+
+    let path = data_dir(&home).join(XDG_FILE_PATH);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| PathError::CreateDirFailed {
+            path: dir.display().to_string(),
+            error: e.to_string(),
+        })?;
+    }
+
+    // one-time move of the legacy ListFile into the data directory
+    let legacy = home.join(LEGACY_FILE_NAME);
+    if !path.exists() && legacy.exists() {
+        migrate_legacy_file(&legacy, &path)?;
+        eprintln!(
+            "Moved your todolists from {} to {}",
+            legacy.display(),
+            path.display()
+        );
+    }
+
+    Ok(path)
+}
+
+/// Base data directory per the XDG spec, defaulting to `~/.local/share`.
+fn data_dir(home: &Path) -> PathBuf {
+    match std::env::var_os("XDG_DATA_HOME").map(PathBuf::from) {
+        // the spec requires relative paths to be ignored
+        Some(dir) if dir.is_absolute() => dir,
+        _ => home.join(".local/share"),
+    }
+}
+
+/// Moves the legacy ListFile to `to`, copying if a rename across filesystems is not possible.
+fn migrate_legacy_file(from: &Path, to: &Path) -> Result<(), PathError> {
+    let failed = |e: std::io::Error| PathError::MigrationFailed {
+        from: from.display().to_string(),
+        to: to.display().to_string(),
+        error: e.to_string(),
+    };
+
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to).map_err(failed)?;
+        std::fs::remove_file(from).map_err(failed)?;
+    }
+    Ok(())
+}
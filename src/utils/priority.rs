@@ -0,0 +1,38 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// How urgent a task is.
+/// Variants are declared from most to least urgent so that sorting puts urgent tasks first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    #[value(alias = "h")]
+    High,
+    #[value(alias = "m", alias = "med")]
+    Medium,
+    #[value(alias = "l")]
+    Low,
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::High => write!(f, "(H)"),
+            Priority::Medium => write!(f, "(M)"),
+            Priority::Low => write!(f, "(L)"),
+        }
+    }
+}
+
+/// Compares optional priorities so that more urgent tasks come first and tasks without a
+/// priority come last.
+pub fn cmp_priority(a: Option<Priority>, b: Option<Priority>) -> std::cmp::Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    }
+}
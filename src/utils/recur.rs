@@ -0,0 +1,281 @@
+use std::fmt::Display;
+
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use self::errors::RecurError;
+
+/// How often a recurring task repeats.
+/// Stored in the same form it is entered on the command line, e.g. `2w` or `1st-monday`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Interval {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    /// Every Monday through Friday
+    Weekday,
+    /// The nth given weekday of every month, where n of 0 means the last one
+    NthWeekday {
+        n: u8,
+        weekday: Weekday,
+    },
+}
+
+/// What the next occurrence of a recurring task is counted from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecurFrom {
+    /// Keep a fixed schedule based on the task's due date
+    #[default]
+    Due,
+    /// Count from the day the task was completed
+    Completion,
+}
+
+/// Recurrence rule of a task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub every: Interval,
+    pub from: RecurFrom,
+}
+
+pub mod errors {
+    use thiserror::Error;
+
+    #[derive(Error, Debug)]
+    pub enum RecurError {
+        /// Given string is not a valid interval
+        #[error("Could not parse recurrence from: {given:?}. Accepted values: day, week, month, weekday, an amount such as 3d, 2w or 6m, or a weekday of the month such as 1st-monday or last-friday")]
+        RecurParseError { given: String },
+    }
+}
+
+impl Interval {
+    /// Finds the first day of this interval strictly after `base`.
+    pub fn next_after(&self, base: NaiveDate) -> NaiveDate {
+        match *self {
+            Interval::Days(n) => base + Days::new(n as u64),
+            Interval::Weeks(n) => base + Days::new(n as u64 * 7),
+            // chrono clamps to the end of shorter months, e.g. Jan 31st + 1 month is Feb 28th
+            Interval::Months(n) => base + Months::new(n),
+            Interval::Weekday => {
+                let mut day = base.succ_opt().unwrap();
+                while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+                    day = day.succ_opt().unwrap();
+                }
+                day
+            }
+            Interval::NthWeekday { n, weekday } => {
+                // try this month first, then move on month by month
+                let first = base.with_day(1).unwrap();
+                (0..)
+                    .filter_map(|i| nth_weekday_of_month(first + Months::new(i), n, weekday))
+                    .find(|day| *day > base)
+                    .unwrap()
+            }
+        }
+    }
+}
+
+/// Finds the nth weekday in the month of `first`, where n of 0 means the last one.
+fn nth_weekday_of_month(first: NaiveDate, n: u8, weekday: Weekday) -> Option<NaiveDate> {
+    if n == 0 {
+        let last = (first + Months::new(1)).pred_opt()?;
+        let back = (7 + last.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+        return Some(last - Days::new(back as u64));
+    }
+    NaiveDate::from_weekday_of_month_opt(first.year(), first.month(), weekday, n)
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interval::Days(n) => write!(f, "{}d", n),
+            Interval::Weeks(n) => write!(f, "{}w", n),
+            Interval::Months(n) => write!(f, "{}m", n),
+            Interval::Weekday => write!(f, "weekday"),
+            Interval::NthWeekday { n, weekday } => {
+                let n = match n {
+                    0 => "last",
+                    1 => "1st",
+                    2 => "2nd",
+                    3 => "3rd",
+                    4 => "4th",
+                    _ => "5th",
+                };
+                write!(f, "{}-{}", n, format!("{:?}", weekday).to_lowercase())
+            }
+        }
+    }
+}
+
+impl From<Interval> for String {
+    fn from(interval: Interval) -> Self {
+        interval.to_string()
+    }
+}
+
+impl TryFrom<String> for Interval {
+    type Error = RecurError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        parse_interval(&s)
+    }
+}
+
+/// Helper which parses a recurrence interval from a string.
+pub fn parse_interval(s: &str) -> Result<Interval, RecurError> {
+    let s = s.trim().to_lowercase();
+    let invalid = || RecurError::RecurParseError { given: s.clone() };
+
+    match s.as_str() {
+        "day" | "daily" => return Ok(Interval::Days(1)),
+        "week" | "weekly" => return Ok(Interval::Weeks(1)),
+        "month" | "monthly" => return Ok(Interval::Months(1)),
+        "weekday" | "weekdays" => return Ok(Interval::Weekday),
+        _ => {}
+    }
+
+    // amounts such as 3d, 2w or 6m
+    let amount_regex = Regex::new(r"^(\d{1,3})([dwm])$").unwrap();
+    if let Some(caps) = amount_regex.captures(&s) {
+        let n = caps[1].parse::<u32>().unwrap();
+        if n == 0 {
+            return Err(invalid());
+        }
+        return Ok(match &caps[2] {
+            "d" => Interval::Days(n),
+            "w" => Interval::Weeks(n),
+            _ => Interval::Months(n),
+        });
+    }
+
+    // weekdays of the month such as 1st-monday or last-fri
+    let nth_regex = Regex::new(r"^(1st|2nd|3rd|4th|5th|last)-([a-z]+)$").unwrap();
+    let caps = nth_regex.captures(&s).ok_or_else(invalid)?;
+    let n = match &caps[1] {
+        "last" => 0,
+        n => n[..1].parse::<u8>().unwrap(),
+    };
+    let weekday = caps[2].parse::<Weekday>().map_err(|_| invalid())?;
+    Ok(Interval::NthWeekday { n, weekday })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn nth(n: u8, weekday: Weekday) -> Interval {
+        Interval::NthWeekday { n, weekday }
+    }
+
+    #[test]
+    fn counts_days_and_weeks() {
+        assert_eq!(
+            Interval::Days(3).next_after(ymd(2026, 12, 30)),
+            ymd(2027, 1, 2)
+        );
+        assert_eq!(
+            Interval::Weeks(2).next_after(ymd(2026, 10, 17)),
+            ymd(2026, 10, 31)
+        );
+    }
+
+    #[test]
+    fn clamps_months_to_their_last_day() {
+        assert_eq!(
+            Interval::Months(1).next_after(ymd(2026, 1, 31)),
+            ymd(2026, 2, 28)
+        );
+        assert_eq!(
+            Interval::Months(1).next_after(ymd(2028, 1, 31)),
+            ymd(2028, 2, 29)
+        );
+        assert_eq!(
+            Interval::Months(3).next_after(ymd(2026, 8, 31)),
+            ymd(2026, 11, 30)
+        );
+        assert_eq!(
+            Interval::Months(12).next_after(ymd(2028, 2, 29)),
+            ymd(2029, 2, 28)
+        );
+    }
+
+    #[test]
+    fn skips_weekends_for_weekdays() {
+        assert_eq!(
+            Interval::Weekday.next_after(ymd(2026, 10, 15)),
+            ymd(2026, 10, 16)
+        );
+        assert_eq!(
+            Interval::Weekday.next_after(ymd(2026, 10, 16)),
+            ymd(2026, 10, 19)
+        );
+        assert_eq!(
+            Interval::Weekday.next_after(ymd(2026, 10, 17)),
+            ymd(2026, 10, 19)
+        );
+        assert_eq!(
+            Interval::Weekday.next_after(ymd(2026, 10, 18)),
+            ymd(2026, 10, 19)
+        );
+    }
+
+    #[test]
+    fn finds_nth_weekdays_of_the_month() {
+        assert_eq!(
+            nth(1, Weekday::Mon).next_after(ymd(2026, 10, 17)),
+            ymd(2026, 11, 2)
+        );
+        assert_eq!(
+            nth(3, Weekday::Sat).next_after(ymd(2026, 10, 16)),
+            ymd(2026, 10, 17)
+        );
+        assert_eq!(
+            nth(3, Weekday::Sat).next_after(ymd(2026, 10, 17)),
+            ymd(2026, 11, 21)
+        );
+    }
+
+    #[test]
+    fn skips_months_without_a_fifth_weekday() {
+        assert_eq!(
+            nth(5, Weekday::Fri).next_after(ymd(2026, 10, 17)),
+            ymd(2026, 10, 30)
+        );
+        assert_eq!(
+            nth(5, Weekday::Fri).next_after(ymd(2026, 10, 30)),
+            ymd(2027, 1, 29)
+        );
+    }
+
+    #[test]
+    fn finds_last_weekdays_of_the_month() {
+        assert_eq!(
+            nth(0, Weekday::Fri).next_after(ymd(2026, 10, 17)),
+            ymd(2026, 10, 30)
+        );
+        assert_eq!(
+            nth(0, Weekday::Fri).next_after(ymd(2026, 10, 30)),
+            ymd(2026, 11, 27)
+        );
+        assert_eq!(
+            nth(0, Weekday::Sat).next_after(ymd(2026, 10, 17)),
+            ymd(2026, 10, 31)
+        );
+        assert_eq!(
+            nth(0, Weekday::Sun).next_after(ymd(2026, 10, 17)),
+            ymd(2026, 10, 25)
+        );
+        assert_eq!(
+            nth(0, Weekday::Mon).next_after(ymd(2026, 2, 1)),
+            ymd(2026, 2, 23)
+        );
+    }
+}
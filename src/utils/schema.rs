@@ -0,0 +1,398 @@
+use chrono::{Datelike, NaiveDate};
+use serde_json::{Map, Value};
+
+use crate::utils::todolist::errors::ListError;
+
+/// Version of the ListFile layout written by this binary.
+/// Bump this and append a migration to `MIGRATIONS` whenever the on-disk layout changes.
+pub const CURRENT_VERSION: u32 = 15;
+
+/// Upgrades a raw ListFile by exactly one version.
+type Migration = fn(&mut Map<String, Value>);
+
+/// Ordered migrations, where `MIGRATIONS[n]` upgrades version `n` to version `n + 1`.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [
+    v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8, v8_to_v9,
+    v9_to_v10, v10_to_v11, v11_to_v12, v12_to_v13, v13_to_v14, v14_to_v15,
+];
+
+/// Reads the layout version of a raw ListFile.
+/// Files written before versioning was introduced have no version field and are version 0.
+pub fn version_of(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |v| v as u32)
+}
+
+/// Upgrades a raw ListFile to `CURRENT_VERSION` one step at a time.
+/// ### Returns
+/// Version the file was stored as, or ListError if the file is newer than this binary understands
+pub fn migrate(value: &mut Value, file_path: &str) -> Result<u32, ListError> {
+    let version = version_of(value);
+    if version > CURRENT_VERSION {
+        return Err(ListError::UnsupportedVersion {
+            path: file_path.to_string(),
+            found: version,
+            supported: CURRENT_VERSION,
+        });
+    }
+
+    let map = value
+        .as_object_mut()
+        .ok_or_else(|| ListError::UnreadableListFile {
+            path: file_path.to_string(),
+            error: "expected a JSON object".to_string(),
+        })?;
+
+    // apply each outstanding migration in order
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(map);
+        map.insert("version".to_string(), Value::from(from as u32 + 1));
+    }
+    Ok(version)
+}
+
+/// Version 1 only introduces the `version` field itself.
+fn v0_to_v1(_: &mut Map<String, Value>) {}
+
+/// Version 2 gives every task a stable ID.
+/// IDs are assigned in display order so the numbers users are used to seeing stay the same.
+fn v1_to_v2(file: &mut Map<String, Value>) {
+    let lists = file.get_mut("lists").and_then(Value::as_object_mut);
+    for list in lists.into_iter().flat_map(|lists| lists.values_mut()) {
+        let Some(list) = list.as_object_mut() else {
+            continue;
+        };
+
+        // order tasks the way v1 displayed them: stable sort by due date, undated first
+        let mut tasks = match list.remove("tasks") {
+            Some(Value::Array(tasks)) => tasks,
+            _ => Vec::new(),
+        };
+        tasks.sort_by_key(|t| v1_date_key(t.get("date")));
+
+        for (i, task) in tasks.iter_mut().enumerate() {
+            if let Some(task) = task.as_object_mut() {
+                task.insert("id".to_string(), Value::from(i as u32 + 1));
+            }
+        }
+        list.insert("next_id".to_string(), Value::from(tasks.len() as u32 + 1));
+        list.insert("tasks".to_string(), Value::Array(tasks));
+    }
+}
+
+/// Version 3 adds an optional priority to every task.
+fn v2_to_v3(file: &mut Map<String, Value>) {
+    for task in tasks_mut(file) {
+        task.entry("priority").or_insert(Value::Null);
+    }
+}
+
+/// Version 4 adds a set of tags to every task.
+fn v3_to_v4(file: &mut Map<String, Value>) {
+    for task in tasks_mut(file) {
+        task.entry("tags").or_insert(Value::Array(Vec::new()));
+    }
+}
+
+/// Version 5 stores due dates as ISO `YYYY-MM-DD` strings instead of `{month, day, year}`.
+/// Dates without a year meant the current year, so they are pinned to the year of the migration.
+/// Days which don't exist in their month (e.g. February 29th of a common year) are clamped to
+/// the last day of the month.
+fn v4_to_v5(file: &mut Map<String, Value>) {
+    for task in tasks_mut(file) {
+        let date = task.get("date").and_then(v4_date);
+        task.insert(
+            "date".to_string(),
+            date.map_or(Value::Null, |d| Value::from(d.to_string())),
+        );
+    }
+}
+
+/// Converts a v4 `{month, day, year}` date into a calendar date.
+fn v4_date(date: &Value) -> Option<NaiveDate> {
+    let (year, month, day) = v1_date_key(Some(date))?;
+    let (year, month) = (year as i32, month as u32);
+    (1..=day.max(1) as u32)
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+}
+
+/// Version 6 allows due dates to be RFC 3339 timestamps carrying a time of day.
+/// Existing day-only dates are still valid, so nothing needs to change.
+fn v5_to_v6(_: &mut Map<String, Value>) {}
+
+/// Version 7 adds an optional recurrence rule to every task.
+fn v6_to_v7(file: &mut Map<String, Value>) {
+    for task in tasks_mut(file) {
+        task.entry("recurrence").or_insert(Value::Null);
+    }
+}
+
+/// Version 8 lets tasks have subtasks.
+fn v7_to_v8(file: &mut Map<String, Value>) {
+    for task in tasks_mut(file) {
+        task.entry("subtasks").or_insert(Value::Array(Vec::new()));
+        task.entry("next_subtask_id").or_insert(Value::from(1));
+    }
+}
+
+/// Version 9 lets tasks wait on other tasks.
+fn v8_to_v9(file: &mut Map<String, Value>) {
+    for_each_task(file, &mut |task| {
+        task.entry("after").or_insert(Value::Array(Vec::new()));
+    });
+}
+
+/// Version 10 replaces the `complete` flag with a status.
+fn v9_to_v10(file: &mut Map<String, Value>) {
+    for_each_task(file, &mut |task| {
+        let complete = task.remove("complete").and_then(|c| c.as_bool());
+        let status = if complete == Some(true) {
+            "done"
+        } else {
+            "todo"
+        };
+        task.entry("status").or_insert(Value::from(status));
+    });
+}
+
+/// Version 11 records when tasks were created, started and completed.
+/// These times are unknown for existing tasks, so they are left empty.
+fn v10_to_v11(file: &mut Map<String, Value>) {
+    for_each_task(file, &mut |task| {
+        for field in ["created", "started", "completed"] {
+            task.entry(field).or_insert(Value::Null);
+        }
+    });
+}
+
+/// Version 12 adds free-form notes to every task.
+fn v11_to_v12(file: &mut Map<String, Value>) {
+    for_each_task(file, &mut |task| {
+        task.entry("notes").or_insert(Value::from(""));
+    });
+}
+
+/// Version 13 lets lists be archived.
+fn v12_to_v13(file: &mut Map<String, Value>) {
+    let lists = file.get_mut("lists").and_then(Value::as_object_mut);
+    for list in lists.into_iter().flat_map(|lists| lists.values_mut()) {
+        if let Some(list) = list.as_object_mut() {
+            list.entry("archived").or_insert(Value::Bool(false));
+        }
+    }
+}
+
+/// Version 14 keeps dropped tasks and deleted lists in a trash.
+fn v13_to_v14(file: &mut Map<String, Value>) {
+    file.entry("trash").or_insert(Value::Array(Vec::new()));
+    file.entry("next_trash_id").or_insert(Value::from(1));
+}
+
+/// Version 15 links closed recurring tasks to the next occurrence added for them.
+/// Occurrences added before this are not linked, as there is no telling which task they follow.
+fn v14_to_v15(file: &mut Map<String, Value>) {
+    for_each_task(file, &mut |task| {
+        task.entry("spawned").or_insert(Value::Null);
+    });
+}
+
+/// Applies `f` to every task object in a raw ListFile, including subtasks at any depth.
+/// Only valid for files of version 8 or later, where subtasks exist.
+fn for_each_task(file: &mut Map<String, Value>, f: &mut impl FnMut(&mut Map<String, Value>)) {
+    fn visit(task: &mut Map<String, Value>, f: &mut impl FnMut(&mut Map<String, Value>)) {
+        f(task);
+        let subtasks = task.get_mut("subtasks").and_then(Value::as_array_mut);
+        for subtask in subtasks
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_object_mut)
+        {
+            visit(subtask, f);
+        }
+    }
+    for task in tasks_mut(file) {
+        visit(task, f);
+    }
+}
+
+/// Every top-level task object in a raw ListFile, including tasks and lists in the trash.
+fn tasks_mut(file: &mut Map<String, Value>) -> Vec<&mut Map<String, Value>> {
+    let mut lists = Vec::new();
+    let mut tasks = Vec::new();
+    for (key, value) in file.iter_mut() {
+        match key.as_str() {
+            "lists" => lists.extend(
+                value
+                    .as_object_mut()
+                    .into_iter()
+                    .flat_map(|l| l.values_mut()),
+            ),
+            "trash" => {
+                // trashed items hold either a single task or a whole list
+                let contents = value
+                    .as_array_mut()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|item| item.get_mut("contents"))
+                    .filter_map(Value::as_object_mut);
+                for (kind, value) in contents.flat_map(|contents| contents.iter_mut()) {
+                    match kind.as_str() {
+                        "task" => tasks.extend(value.as_object_mut()),
+                        "list" => lists.push(value),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let listed = lists
+        .into_iter()
+        .filter_map(|list| list.get_mut("tasks"))
+        .filter_map(Value::as_array_mut)
+        .flat_map(|tasks| tasks.iter_mut())
+        .filter_map(Value::as_object_mut);
+    tasks.extend(listed);
+    tasks
+}
+
+/// Sort key of a v1 `{month, day, year}` date, where a missing year means the current year.
+fn v1_date_key(date: Option<&Value>) -> Option<(u64, u64, u64)> {
+    let date = date?.as_object()?;
+    let field = |name: &str| date.get(name).and_then(Value::as_u64);
+    let year = field("year").unwrap_or(chrono::Utc::now().year() as u64);
+    Some((year, field("month")?, field("day")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::utils::{status::Status, task_ref::TaskPath, todolist::ListFile};
+
+    /// ListFile as written before versioning, with `{month, day, year}` dates and a
+    /// `complete` flag on each task.
+    fn v0_file() -> Value {
+        json!({
+            "focused": "home",
+            "lists": {
+                "home": {
+                    "name": "home",
+                    "tasks": [
+                        {
+                            "title": "leap day",
+                            "date": {"month": 2, "day": 29, "year": 2023},
+                            "complete": true
+                        },
+                        {"title": "undated", "date": null, "complete": false},
+                        {
+                            "title": "this year",
+                            "date": {"month": 3, "day": 17, "year": null},
+                            "complete": false
+                        }
+                    ]
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn migrates_v0_to_current() {
+        let mut value = v0_file();
+        assert_eq!(migrate(&mut value, "test").unwrap(), 0);
+        assert_eq!(version_of(&value), CURRENT_VERSION);
+
+        let (list_file, version) = ListFile::from_value(value, "test").unwrap();
+        assert_eq!(version, CURRENT_VERSION);
+        assert_eq!(list_file.focused.as_deref(), Some("home"));
+        assert!(list_file.trash.is_empty());
+
+        let list = &list_file.lists["home"];
+        assert_eq!(list.next_id, 4);
+        assert!(!list.archived);
+        for task in &list.tasks {
+            assert!(task.subtasks.is_empty());
+            assert!(task.tags.is_empty());
+            assert!(task.after.is_empty());
+            assert_eq!(task.priority, None);
+            assert_eq!(task.created, None);
+            assert_eq!(task.notes, "");
+        }
+    }
+
+    #[test]
+    fn assigns_ids_in_display_order() {
+        let (list_file, _) = ListFile::from_value(v0_file(), "test").unwrap();
+        let list = &list_file.lists["home"];
+
+        // undated tasks were shown first, then by due date
+        let title = |id| list.get_task(&TaskPath::top(id)).unwrap().title.as_str();
+        assert_eq!(title(1), "undated");
+        assert_eq!(title(2), "leap day");
+        assert_eq!(title(3), "this year");
+    }
+
+    #[test]
+    fn clamps_days_missing_from_their_month() {
+        let (list_file, _) = ListFile::from_value(v0_file(), "test").unwrap();
+        let task = list_file.lists["home"].get_task(&TaskPath::top(2)).unwrap();
+
+        let date = task.date.unwrap();
+        assert_eq!(date.day(), NaiveDate::from_ymd_opt(2023, 2, 28).unwrap());
+        assert_eq!(date.time(), None);
+    }
+
+    #[test]
+    fn pins_dates_without_a_year_to_the_current_year() {
+        let (list_file, _) = ListFile::from_value(v0_file(), "test").unwrap();
+        let task = list_file.lists["home"].get_task(&TaskPath::top(3)).unwrap();
+
+        let year = chrono::Utc::now().year();
+        let expected = NaiveDate::from_ymd_opt(year, 3, 17).unwrap();
+        assert_eq!(task.date.unwrap().day(), expected);
+    }
+
+    #[test]
+    fn maps_complete_flag_to_status() {
+        let mut value = v0_file();
+        migrate(&mut value, "test").unwrap();
+        for task in value["lists"]["home"]["tasks"].as_array().unwrap() {
+            assert!(task.get("complete").is_none());
+        }
+
+        let (list_file, _) = ListFile::from_value(value, "test").unwrap();
+        let status = |id| {
+            list_file.lists["home"]
+                .get_task(&TaskPath::top(id))
+                .unwrap()
+                .status
+        };
+        assert_eq!(status(1), Status::Todo);
+        assert_eq!(status(2), Status::Done);
+        assert_eq!(status(3), Status::Todo);
+    }
+
+    #[test]
+    fn leaves_current_files_unchanged() {
+        let mut value = v0_file();
+        migrate(&mut value, "test").unwrap();
+        let migrated = value.clone();
+
+        assert_eq!(migrate(&mut value, "test").unwrap(), CURRENT_VERSION);
+        assert_eq!(value, migrated);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let mut value = json!({"version": CURRENT_VERSION + 1, "lists": {}});
+        assert!(matches!(
+            migrate(&mut value, "test"),
+            Err(ListError::UnsupportedVersion { found, .. }) if found == CURRENT_VERSION + 1
+        ));
+    }
+}
@@ -0,0 +1,353 @@
+use regex::Regex;
+
+use self::errors::SelectorError;
+use crate::utils::{
+    status::Status,
+    tags::normalize_tag,
+    task_ref::{parse_task_path, TaskPath, TaskRef},
+    todolist::{errors::ListError, Task, TodoList},
+};
+
+/// Set of tasks given on the command line, optionally naming the list they are in,
+/// e.g. `3`, `1-5`, `3,7,9-12`, `done`, `/regex/`, `tag:x` or `work:overdue`.
+#[derive(Clone, Debug)]
+pub struct Selector {
+    /// List the tasks are in, or None for the list the command applies to
+    pub list: Option<String>,
+    /// Comma-separated parts of the selector, each adding tasks to the set
+    terms: Vec<Term>,
+}
+
+/// A single part of a selector.
+#[derive(Clone, Debug)]
+enum Term {
+    /// One task, e.g. `3` or `3.1`
+    Task(TaskPath),
+    /// Top-level tasks with IDs in the inclusive range, e.g. `1-5`
+    Range(u32, u32),
+    /// Every task
+    All,
+    /// Tasks marked done
+    Done,
+    /// Unfinished tasks whose due date has passed
+    Overdue,
+    /// Tasks with titles matching the regex, e.g. `/deploy/`
+    Pattern(Regex),
+    /// Tasks with the tag, e.g. `tag:review`
+    Tag(String),
+}
+
+pub mod errors {
+    use thiserror::Error;
+
+    #[derive(Error, Debug)]
+    pub enum SelectorError {
+        /// Given string is not a valid selector
+        #[error("Could not parse task selector from: {given:?}. Expected IDs such as 3, 3.1 or 1-5, all, done, overdue, /regex/ or tag:x, separated by commas and optionally prefixed with a list name as in work:1-5")]
+        SelectorParseError { given: String },
+        /// Range runs backwards
+        #[error("Invalid range {given:?}, the first ID must not be greater than the last")]
+        BackwardsRange { given: String },
+        /// Pattern is not a valid regex
+        #[error("Invalid pattern {given:?}: {error}")]
+        InvalidPattern { given: String, error: String },
+    }
+}
+
+impl From<TaskRef> for Selector {
+    fn from(r: TaskRef) -> Self {
+        Self {
+            list: r.list,
+            terms: vec![Term::Task(r.path)],
+        }
+    }
+}
+
+impl Selector {
+    /// Resolves the selector against the given list. If `position` is set, IDs and ranges refer to
+    /// positions of top-level tasks in the displayed list instead.
+    /// ### Returns
+    /// Paths of the selected tasks without duplicates, in the order they were selected,
+    /// or ListError if a position is out of range or names a subtask, or a range starts at 0
+    pub fn resolve(&self, list: &TodoList, position: bool) -> Result<Vec<TaskPath>, ListError> {
+        let mut paths = Vec::new();
+        for term in &self.terms {
+            let selected = match term {
+                Term::Range(0, _) => return Err(ListError::ZeroIndex),
+                Term::Task(path) if position && path.ids().len() > 1 => {
+                    return Err(ListError::SubtaskPosition {
+                        given: path.clone(),
+                    })
+                }
+                Term::Task(path) if position => {
+                    list.paths_from_positions([path.ids()[0] as usize])?
+                }
+                Term::Task(path) => vec![path.clone()],
+                Term::Range(start, end) if position => {
+                    list.paths_from_positions((*start..=*end).map(|p| p as usize))?
+                }
+                Term::Range(start, end) => list
+                    .tasks
+                    .iter()
+                    .filter(|t| (*start..=*end).contains(&t.id))
+                    .map(|t| TaskPath::top(t.id))
+                    .collect(),
+                _ => list.select(|t| term.matches(t)),
+            };
+
+            for path in selected {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        Ok(paths)
+    }
+}
+
+impl Term {
+    /// Returns whether the task is picked by this term, for terms which don't name tasks by ID.
+    fn matches(&self, task: &Task) -> bool {
+        match self {
+            Term::Task(_) | Term::Range(..) => false,
+            Term::All => true,
+            Term::Done => task.status == Status::Done,
+            Term::Overdue => {
+                !task.status.is_closed() && task.date.is_some_and(|date| date.is_past())
+            }
+            Term::Pattern(regex) => regex.is_match(&task.title),
+            Term::Tag(tag) => task.tags.contains(tag),
+        }
+    }
+}
+
+/// Helper which parses a selector such as `3`, `1-5`, `3,7,9-12`, `done` or `work:tag:x` from a string.
+pub fn parse_selector(s: &str) -> Result<Selector, SelectorError> {
+    let invalid = || SelectorError::SelectorParseError {
+        given: s.to_string(),
+    };
+
+    // a list prefix must be a valid list name, so patterns and tag terms aren't mistaken for one
+    let list_name = Regex::new(r"^[a-zA-Z0-9]([a-zA-Z0-9-_]*[a-zA-Z0-9])?$").unwrap();
+    let (list, rest) = match s.split_once(':') {
+        Some((list, rest)) if list != "tag" && list_name.is_match(list) => {
+            (Some(list.to_string()), rest)
+        }
+        _ => (None, s),
+    };
+
+    let terms = split_terms(rest)
+        .into_iter()
+        .map(|term| parse_term(term).map_err(|e| e.unwrap_or_else(invalid)))
+        .collect::<Result<Vec<Term>, SelectorError>>()?;
+    if terms.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Selector { list, terms })
+}
+
+/// Splits a selector into its comma-separated terms, leaving commas within `/regex/` terms alone.
+/// A pattern runs up to the first `/` which is followed by a comma or ends the selector.
+fn split_terms(s: &str) -> Vec<&str> {
+    let mut terms = Vec::new();
+    let mut rest = s;
+    while !rest.is_empty() {
+        let end = match rest.strip_prefix('/') {
+            Some(pattern) => match pattern.find("/,") {
+                Some(i) => i + 2,
+                None => rest.len(),
+            },
+            None => rest.find(',').unwrap_or(rest.len()),
+        };
+        terms.push(&rest[..end]);
+        rest = rest[end..].strip_prefix(',').unwrap_or(&rest[end..]);
+    }
+    terms
+}
+
+/// Parses a single selector term.
+/// ### Returns
+/// Term, or the specific problem with it if there is one beyond not being a term at all
+fn parse_term(s: &str) -> Result<Term, Option<SelectorError>> {
+    match s {
+        "all" => return Ok(Term::All),
+        "done" => return Ok(Term::Done),
+        "overdue" => return Ok(Term::Overdue),
+        _ => {}
+    }
+
+    if let Some(tag) = s.strip_prefix("tag:") {
+        let tag = normalize_tag(tag);
+        if tag.is_empty() {
+            return Err(None);
+        }
+        return Ok(Term::Tag(tag));
+    }
+
+    if let Some(pattern) = s.strip_prefix('/').and_then(|p| p.strip_suffix('/')) {
+        return Regex::new(pattern).map(Term::Pattern).map_err(|e| {
+            Some(SelectorError::InvalidPattern {
+                given: pattern.to_string(),
+                error: e.to_string(),
+            })
+        });
+    }
+
+    if let Some((start, end)) = s.split_once('-') {
+        let (start, end) = match (start.parse::<u32>(), end.parse::<u32>()) {
+            (Ok(start), Ok(end)) => (start, end),
+            _ => return Err(None),
+        };
+        if start > end {
+            return Err(Some(SelectorError::BackwardsRange {
+                given: s.to_string(),
+            }));
+        }
+        return Ok(Term::Range(start, end));
+    }
+
+    parse_task_path(s).map(Term::Task).map_err(|_| None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::utils::date::parse_date;
+
+    /// List of `deploy site` with subtask `kid`, `write docs #review` (done), `fix, bug` and an
+    /// overdue `old report`, with IDs 1 to 4 in display order.
+    fn list() -> TodoList {
+        let mut list = TodoList::new("work".to_string());
+        let titles = ["deploy site", "write docs #review", "fix, bug"];
+        list.add_tasks(
+            titles.iter().map(|t| t.to_string()).collect(),
+            None,
+            None,
+            &BTreeSet::new(),
+            None,
+            None,
+        )
+        .unwrap();
+        list.add_tasks(
+            vec!["old report".to_string()],
+            Some(parse_date("2020-01-01").unwrap()),
+            None,
+            &BTreeSet::new(),
+            None,
+            None,
+        )
+        .unwrap();
+        list.add_tasks(
+            vec!["kid".to_string()],
+            None,
+            None,
+            &BTreeSet::new(),
+            None,
+            Some(&TaskPath::top(1)),
+        )
+        .unwrap();
+        list.update_statuses(vec![TaskPath::top(2)], Status::Done, false)
+            .unwrap();
+        list
+    }
+
+    /// Resolves the selector against `list()`, as paths such as "1.1".
+    fn resolve(s: &str, position: bool) -> Result<Vec<String>, ListError> {
+        let paths = parse_selector(s).unwrap().resolve(&list(), position)?;
+        Ok(paths.iter().map(TaskPath::to_string).collect())
+    }
+
+    #[test]
+    fn parses_list_prefixes() {
+        assert_eq!(
+            parse_selector("work:1-5").unwrap().list.as_deref(),
+            Some("work")
+        );
+        assert_eq!(
+            parse_selector("work:tag:x").unwrap().list.as_deref(),
+            Some("work")
+        );
+        assert_eq!(parse_selector("tag:x").unwrap().list, None);
+        assert_eq!(parse_selector("/a:b/").unwrap().list, None);
+        assert_eq!(parse_selector("3,tag:x").unwrap().list, None);
+    }
+
+    #[test]
+    fn keeps_commas_inside_patterns() {
+        assert_eq!(split_terms("/a,b/,3"), vec!["/a,b/", "3"]);
+        assert_eq!(split_terms("/a/,/b/"), vec!["/a/", "/b/"]);
+        assert_eq!(split_terms("1,2-4,done"), vec!["1", "2-4", "done"]);
+    }
+
+    #[test]
+    fn rejects_invalid_selectors() {
+        assert!(matches!(
+            parse_selector("5-3"),
+            Err(SelectorError::BackwardsRange { .. })
+        ));
+        assert!(matches!(
+            parse_selector("/(/"),
+            Err(SelectorError::InvalidPattern { .. })
+        ));
+        for s in ["", "x:y:z", "3,,4", "tag:", "1-", "-2", "soon", "/open"] {
+            assert!(
+                matches!(
+                    parse_selector(s),
+                    Err(SelectorError::SelectorParseError { .. })
+                ),
+                "{s:?} should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn resolves_ids_and_ranges() {
+        assert_eq!(resolve("3", false).unwrap(), ["3"]);
+        assert_eq!(resolve("1.1", false).unwrap(), ["1.1"]);
+        assert_eq!(resolve("1-3", false).unwrap(), ["1", "2", "3"]);
+        assert_eq!(resolve("3,1-2,2", false).unwrap(), ["3", "1", "2"]);
+        // ranges only pick tasks which exist
+        assert_eq!(resolve("3-9", false).unwrap(), ["3", "4"]);
+    }
+
+    #[test]
+    fn resolves_keywords_patterns_and_tags() {
+        assert_eq!(resolve("all", false).unwrap(), ["1", "1.1", "2", "3", "4"]);
+        assert_eq!(resolve("done", false).unwrap(), ["2"]);
+        assert_eq!(resolve("overdue", false).unwrap(), ["4"]);
+        assert_eq!(resolve("/^d/", false).unwrap(), ["1"]);
+        assert_eq!(resolve("/, b/", false).unwrap(), ["3"]);
+        assert_eq!(resolve("tag:Review", false).unwrap(), ["2"]);
+        assert!(resolve("tag:none", false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn resolves_positions_in_the_order_given() {
+        assert_eq!(resolve("1-3", true).unwrap(), ["1", "2", "3"]);
+        assert_eq!(resolve("4,1", true).unwrap(), ["4", "1"]);
+    }
+
+    #[test]
+    fn rejects_invalid_positions_and_zero() {
+        assert!(matches!(
+            resolve("3.1", true),
+            Err(ListError::SubtaskPosition { .. })
+        ));
+        assert!(matches!(
+            resolve("9", true),
+            Err(ListError::PositionOutOfRange {
+                position: 9,
+                len: 4
+            })
+        ));
+        assert!(matches!(
+            resolve("2-4000000000", true),
+            Err(ListError::PositionOutOfRange { position: 5, .. })
+        ));
+        assert!(matches!(resolve("0", true), Err(ListError::ZeroIndex)));
+        assert!(matches!(resolve("0-2", true), Err(ListError::ZeroIndex)));
+        assert!(matches!(resolve("0-2", false), Err(ListError::ZeroIndex)));
+    }
+}
@@ -0,0 +1,47 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Where a task stands in its lifecycle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    /// Not started yet
+    #[default]
+    Todo,
+    /// Currently being worked on
+    InProgress,
+    /// Waiting on someone or something outside the list
+    Waiting,
+    /// Cannot progress until something is resolved
+    Blocked,
+    /// No longer needs doing
+    Cancelled,
+    Done,
+}
+
+impl Status {
+    /// Returns whether no more work is expected on a task with this status.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Status::Done | Status::Cancelled)
+    }
+
+    /// Returns whether a task with this status could be picked up right now.
+    pub fn is_actionable(&self) -> bool {
+        matches!(self, Status::Todo | Status::InProgress)
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Todo => write!(f, "✕"),
+            Status::InProgress => write!(f, "▶"),
+            Status::Waiting => write!(f, "⧗"),
+            Status::Blocked => write!(f, "⊘"),
+            Status::Cancelled => write!(f, "—"),
+            Status::Done => write!(f, "✓"),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::BTreeSet;
+
+use regex::Regex;
+
+/// Splits `+tag` and `#tag` tokens out of a task title.
+/// ### Returns
+/// Title with the tag tokens removed and the set of tags found
+pub fn extract_tags(title: &str) -> (String, BTreeSet<String>) {
+    // tags are whole words made of letters, numbers, hyphens and underscores, starting with a
+    // letter so issue references such as #482 stay in the title
+    let regex = Regex::new(r"^[+#]([A-Za-z][\w-]*)$").unwrap();
+
+    let mut tags = BTreeSet::new();
+    let mut words = Vec::new();
+    for word in title.split_whitespace() {
+        match regex.captures(word) {
+            Some(caps) => {
+                tags.insert(caps[1].to_lowercase());
+            }
+            None => words.push(word),
+        }
+    }
+
+    // a title made up only of tags keeps its text so the task is never left blank
+    if words.is_empty() {
+        return (title.trim().to_string(), tags);
+    }
+    (words.join(" "), tags)
+}
+
+/// Normalizes a tag given on the command line, e.g. `#Review` becomes `review`.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim_start_matches(['#', '+']).to_lowercase()
+}
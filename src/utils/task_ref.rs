@@ -0,0 +1,138 @@
+use std::fmt::Display;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use self::errors::TaskRefError;
+
+/// Address of a task within a TodoList, e.g. `3` for a task or `3.1` for its first subtask.
+/// Each part is the ID of a task among its siblings.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TaskPath(Vec<u32>);
+
+impl TaskPath {
+    /// Path of a top-level task.
+    pub fn top(id: u32) -> Self {
+        TaskPath(vec![id])
+    }
+
+    /// Path of a subtask of this task.
+    pub fn child(&self, id: u32) -> Self {
+        let mut ids = self.0.clone();
+        ids.push(id);
+        TaskPath(ids)
+    }
+
+    /// IDs making up the path, from the top-level task down.
+    pub fn ids(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// Splits the path into the path of the parent and the ID of the task among its siblings.
+    /// Top-level tasks have an empty parent path.
+    pub fn split(&self) -> (&[u32], u32) {
+        let (id, parent) = self.0.split_last().unwrap();
+        (parent, *id)
+    }
+
+    /// Moves this path from under `from` to under `to`.
+    /// ### Returns
+    /// The moved path, or None if this is neither `from` nor one of its subtasks
+    pub fn rebase(&self, from: &TaskPath, to: &TaskPath) -> Option<TaskPath> {
+        let rest = self.0.strip_prefix(from.0.as_slice())?;
+        Some(TaskPath([to.0.as_slice(), rest].concat()))
+    }
+
+    /// Paths of every ancestor of this task, closest first.
+    pub fn ancestors(&self) -> impl Iterator<Item = TaskPath> + '_ {
+        (1..self.0.len())
+            .rev()
+            .map(|len| TaskPath(self.0[..len].to_vec()))
+    }
+}
+
+impl Display for TaskPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ids: Vec<String> = self.0.iter().map(u32::to_string).collect();
+        write!(f, "{}", ids.join("."))
+    }
+}
+
+/// Reference to a task given on the command line, optionally naming the list it is in,
+/// e.g. `3.1`, or `work:3.1` for a task in the list named `work`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskRef {
+    /// List the task is in, or None for the list the command applies to
+    pub list: Option<String>,
+    pub path: TaskPath,
+}
+
+impl Display for TaskRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.list {
+            Some(list) => write!(f, "{}:{}", list, self.path),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+impl From<TaskPath> for String {
+    fn from(path: TaskPath) -> Self {
+        path.to_string()
+    }
+}
+
+impl TryFrom<String> for TaskPath {
+    type Error = TaskRefError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        parse_task_path(&s)
+    }
+}
+
+pub mod errors {
+    use thiserror::Error;
+
+    #[derive(Error, Debug)]
+    pub enum TaskRefError {
+        /// Given string is not a valid task address
+        #[error("Could not parse task ID from: {given:?}. Expected an ID such as 3, 3.1 for a subtask, or work:3 for a task in another list")]
+        TaskRefParseError { given: String },
+    }
+}
+
+/// Helper which parses a task path such as `3` or `3.1.2` from a string.
+pub fn parse_task_path(s: &str) -> Result<TaskPath, TaskRefError> {
+    let regex = Regex::new(r"^\d+(\.\d+)*$").unwrap();
+    let invalid = || TaskRefError::TaskRefParseError {
+        given: s.to_string(),
+    };
+    if !regex.is_match(s) {
+        return Err(invalid());
+    }
+
+    s.split('.')
+        .map(|id| id.parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<u32>, TaskRefError>>()
+        .map(TaskPath)
+}
+
+/// Helper which parses a task reference such as `3`, `3.1` or `work:3` from a string.
+pub fn parse_task_ref(s: &str) -> Result<TaskRef, TaskRefError> {
+    let (list, path) = match s.split_once(':') {
+        Some((list, path)) if !list.is_empty() => (Some(list.to_string()), path),
+        Some(_) => {
+            return Err(TaskRefError::TaskRefParseError {
+                given: s.to_string(),
+            })
+        }
+        None => (None, s),
+    };
+
+    // report the whole reference rather than just the ID part
+    let path = parse_task_path(path).map_err(|_| TaskRefError::TaskRefParseError {
+        given: s.to_string(),
+    })?;
+    Ok(TaskRef { list, path })
+}
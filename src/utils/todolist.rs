@@ -1,17 +1,36 @@
 use self::errors::ListError;
+use chrono::{DateTime, FixedOffset, Local};
+use clap::ValueEnum;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fmt::Display,
-    io::{self, Write},
+    fs::File,
+    io::{self, IsTerminal, Write},
+    path::Path,
 };
 
-use crate::utils::date::Date;
+use crate::utils::{
+    date::Date,
+    priority::{cmp_priority, Priority},
+    recur::{RecurFrom, Recurrence},
+    schema::{self, CURRENT_VERSION},
+    status::Status,
+    tags::extract_tags,
+    task_ref::TaskPath,
+    trash::{TrashItem, Trashed},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListFile {
+    pub version: u32,
     pub focused: Option<String>,
     pub lists: HashMap<String, TodoList>,
+    /// Dropped tasks and deleted lists which can still be restored
+    pub trash: Vec<TrashItem>,
+    /// ID given to the next item put in the trash
+    pub next_trash_id: u32,
 }
 
 impl ListFile {
@@ -21,12 +40,17 @@ impl ListFile {
     /// New ListFile instance
     pub fn new() -> Self {
         ListFile {
+            version: CURRENT_VERSION,
             focused: None,
             lists: HashMap::new(),
+            trash: Vec::new(),
+            next_trash_id: 1,
         }
     }
 
     /// Read from file and deserialize ListFile.
+    /// Falls back to the rolling backup written by `to_file` if the primary file cannot be read.
+    /// Files in an older layout are migrated and rewritten, keeping a copy of the original.
     /// ### Returns
     /// New ListFile instance from file
     pub fn from_file(file_path: &str) -> Self {
@@ -39,18 +63,116 @@ impl ListFile {
             std::process::exit(1)
         }
 
-        // read file contents and deserialize
-        let contents =
-            std::fs::read_to_string(file_path).expect("Error: failed to read ListFile from file");
-        serde_json::from_str(&contents).expect("Error: failed to deserialize ListFile")
+        // attempt to read the primary file, falling back to the last good backup
+        let (list_file, source_path, version) = match Self::read(file_path) {
+            Ok((list_file, version)) => (list_file, file_path.to_string(), version),
+            // never fall back past a file this binary is too old to understand
+            Err(e @ ListError::UnsupportedVersion { .. }) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1)
+            }
+            Err(e) => {
+                let backup_path = Self::backup_path(file_path);
+                match Self::read(&backup_path) {
+                    Ok((list_file, version)) => {
+                        eprintln!("Warning: {}", e);
+                        eprintln!("Warning: recovered todolists from backup {}", backup_path);
+                        (list_file, backup_path, version)
+                    }
+                    Err(_) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1)
+                    }
+                }
+            }
+        };
+
+        // persist migrated layout, keeping the pre-migration file around
+        if version < CURRENT_VERSION {
+            let pre_migration_path = format!("{}.v{}.bak", file_path, version);
+            std::fs::copy(&source_path, &pre_migration_path)
+                .expect("Error: failed to back up ListFile before migration");
+            list_file.to_file(file_path);
+            eprintln!(
+                "Upgraded todolists from format v{} to v{} (previous file kept at {})",
+                version, CURRENT_VERSION, pre_migration_path
+            );
+        }
+        list_file
     }
 
     /// Serializes ListFile and writes it to file.
+    /// The write goes to a temporary file which is synced and renamed over the old file, so a crash
+    /// mid-write never leaves a truncated ListFile behind. The previous contents are kept as a backup.
     pub fn to_file(&self, file_path: &str) {
         let encoded = serde_json::to_string(&self).expect("Error: failed to serialize ListFile");
-        // overwrite old file
-        std::fs::write(file_path, encoded)
+
+        // write and sync the new contents to a temporary sibling file
+        let tmp_path = format!("{}.tmp", file_path);
+        let mut tmp = File::create(&tmp_path).expect("Error: failed to create temporary ListFile");
+        tmp.write_all(encoded.as_bytes())
             .expect("Error: failed to write serialized ListFile to file");
+        tmp.sync_all()
+            .expect("Error: failed to sync serialized ListFile to disk");
+
+        // keep the old file as a backup, but only if it is still readable
+        if Self::read(file_path).is_ok() {
+            std::fs::copy(file_path, Self::backup_path(file_path))
+                .expect("Error: failed to back up ListFile");
+        }
+
+        // atomically replace old file
+        std::fs::rename(&tmp_path, file_path).expect("Error: failed to replace ListFile");
+        // sync the parent directory so the rename itself survives a crash
+        let dir = match Path::new(file_path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    /// Path of the rolling backup kept alongside the given ListFile.
+    fn backup_path(file_path: &str) -> String {
+        format!("{}.bak", file_path)
+    }
+
+    /// Reads, migrates and deserializes a ListFile without exiting on failure.
+    /// ### Returns
+    /// ListFile and the layout version it was stored as, or ListError describing why it could not be read
+    fn read(file_path: &str) -> Result<(Self, u32), ListError> {
+        Self::from_value(Self::read_raw(file_path)?, file_path)
+    }
+
+    /// Reads a ListFile as raw JSON, exactly as it is stored.
+    /// ### Returns
+    /// JSON value or ListError describing why it could not be read
+    pub fn read_raw(file_path: &str) -> Result<serde_json::Value, ListError> {
+        let unreadable = |error: String| ListError::UnreadableListFile {
+            path: file_path.to_string(),
+            error,
+        };
+        let contents = std::fs::read_to_string(file_path).map_err(|e| unreadable(e.to_string()))?;
+        serde_json::from_str(&contents).map_err(|e| unreadable(e.to_string()))
+    }
+
+    /// Migrates and deserializes a raw ListFile, such as one read by `read_raw`.
+    /// `file_path` is only used to describe errors.
+    /// ### Returns
+    /// ListFile and the layout version it was stored as, or ListError
+    pub fn from_value(
+        mut value: serde_json::Value,
+        file_path: &str,
+    ) -> Result<(Self, u32), ListError> {
+        // bring older layouts up to date before deserializing
+        let version = schema::migrate(&mut value, file_path)?;
+        let list_file =
+            serde_json::from_value(value).map_err(|e| ListError::UnreadableListFile {
+                path: file_path.to_string(),
+                error: e.to_string(),
+            })?;
+        Ok((list_file, version))
     }
 
     /// Create new list within the ListFile.
@@ -75,11 +197,11 @@ impl ListFile {
         Ok(())
     }
 
-    /// Delete the given list from the ListFile.
-    /// Confirms user selection with a list name retype.
+    /// Delete the given list from the ListFile, moving it to the trash.
+    /// The user is expected to have confirmed with `confirm_list_deletion` first.
     /// ### Returns
-    /// Result indicating success of list deletion
-    pub fn delete_list(&mut self, name: &str) -> Result<(), ListError> {
+    /// ID of the list in the trash or ListError
+    pub fn delete_list(&mut self, name: &str) -> Result<u32, ListError> {
         // confirm that the list exists
         if !self.lists.contains_key(name) {
             return Err(ListError::NonexistentListName {
@@ -87,39 +209,277 @@ impl ListFile {
             });
         }
 
-        // prompt user to confirm delete
-        print!("Please confirm list deletion by re-typing the list name: ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim(); // remove whitespace
+        // delete list
+        let list = self.lists.remove(name).unwrap();
+        let id = self.put_in_trash(Trashed::List { list });
+
+        // if no focus, or focused list is the one being deleted, shift focus
+        self.refocus();
+        Ok(id)
+    }
+
+    /// Moves tasks dropped from the given list to the trash.
+    /// ### Returns
+    /// IDs of the tasks in the trash, in the order they were given
+    pub fn trash_tasks(&mut self, list: &str, dropped: Vec<(TaskPath, Task)>) -> Vec<u32> {
+        dropped
+            .into_iter()
+            .map(|(path, task)| {
+                self.put_in_trash(Trashed::Task {
+                    list: list.to_string(),
+                    path,
+                    task: Box::new(task),
+                })
+            })
+            .collect()
+    }
+
+    /// Adds an item to the trash, stamped with the current time.
+    fn put_in_trash(&mut self, contents: Trashed) -> u32 {
+        let id = self.next_trash_id;
+        self.next_trash_id += 1;
+        self.trash.push(TrashItem {
+            id,
+            deleted: Local::now().fixed_offset(),
+            contents,
+        });
+        id
+    }
+
+    /// Puts an item from the trash back where it came from.
+    /// Tasks return to their original place if their parent task still exists, and otherwise
+    /// become top-level tasks of their list.
+    /// ### Returns
+    /// Description of what was restored or ListError
+    pub fn restore(&mut self, id: u32) -> Result<String, ListError> {
+        let index = self
+            .trash
+            .iter()
+            .position(|item| item.id == id)
+            .ok_or(ListError::NonexistentTrashItem { id })?;
+
+        // confirm the item has somewhere to go before taking it out of the trash
+        match &self.trash[index].contents {
+            Trashed::Task { list, .. } if !self.lists.contains_key(list) => {
+                return Err(ListError::NonexistentListName { name: list.clone() })
+            }
+            Trashed::List { list } if self.lists.contains_key(&list.name) => {
+                return Err(ListError::DuplicateListName {
+                    name: list.name.clone(),
+                })
+            }
+            _ => {}
+        }
+
+        match self.trash.remove(index).contents {
+            Trashed::Task { list, path, task } => {
+                let path = self
+                    .lists
+                    .get_mut(&list)
+                    .unwrap()
+                    .restore_task(&path, *task);
+                let task = self.lists[&list].get_task(&path)?;
+                Ok(format!("task {}:{}| {}", list, path, task))
+            }
+            Trashed::List { list } => {
+                let name = list.name.clone();
+                self.lists.insert(name.clone(), list);
+                self.refocus();
+                Ok(format!("list '{}'", name))
+            }
+        }
+    }
+
+    /// Permanently deletes everything in the trash.
+    /// ### Returns
+    /// Number of items deleted
+    pub fn empty_trash(&mut self) -> usize {
+        let count = self.trash.len();
+        self.trash.clear();
+        count
+    }
+
+    /// Permanently deletes items which have been in the trash for longer than `days`.
+    pub fn purge_trash(&mut self, days: u32) {
+        self.trash.retain(|item| !item.is_expired(days));
+    }
+
+    /// Print the items in the trash, oldest first.
+    pub fn print_trash(&self) {
+        // widest ID determines how IDs are spaced
+        let width = self
+            .trash
+            .iter()
+            .map(|item| item.id.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        println!("-- trash --");
+        for item in &self.trash {
+            let deleted = item.deleted.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+            println!("{: <width$}| {}  {}", item.id, deleted, item.contents);
+        }
+    }
+
+    /// Moves focus to the first list in alphabetical order if the focused list is missing or
+    /// archived. Nothing is focused if every list is archived.
+    fn refocus(&mut self) {
+        let valid = |focused: &String| self.lists.get(focused).is_some_and(|l| !l.archived);
+        if self.focused.as_ref().is_some_and(valid) {
+            return;
+        }
+
+        // sort remaining lists
+        let mut names: Vec<&String> = self
+            .lists
+            .iter()
+            .filter(|(_, list)| !list.archived)
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        // set new focus
+        self.focused = names.first().map(|name| name.to_string());
+    }
 
-        // ensure input matches
-        if input != name {
-            return Err(ListError::FailedDeleteConfirmation {
-                entered: input.to_string(),
-                requested: name.to_string(),
+    /// Renames a list, keeping it focused if it was.
+    /// ### Returns
+    /// Result indicating success of the rename
+    pub fn rename_list(&mut self, old: &str, new: &str) -> Result<(), ListError> {
+        // confirm that the list exists and the new name is unique
+        if self.lists.contains_key(new) {
+            return Err(ListError::DuplicateListName {
+                name: new.to_string(),
             });
         }
+        let mut list = self
+            .lists
+            .remove(old)
+            .ok_or_else(|| ListError::NonexistentListName {
+                name: old.to_string(),
+            })?;
 
-        // delete list
-        self.lists.remove(name);
+        // rename list
+        list.name = new.to_string();
+        self.lists.insert(new.to_string(), list);
 
-        // if no focus, or focused list is the one being deleted, shift focus
-        if self.focused.is_none() || self.focused.as_ref().unwrap() == name {
-            // sort remaining lists
-            let mut names: Vec<&String> = self.lists.keys().collect();
-            names.sort();
-            // set new focus
-            if names.is_empty() {
-                self.focused = None;
-            } else {
-                self.focused = Some(names.first().unwrap().to_string());
+        // follow the list with focus, and with tasks dropped from it so they can still be restored
+        if self.focused.as_deref() == Some(old) {
+            self.focused = Some(new.to_string());
+        }
+        for item in &mut self.trash {
+            if let Trashed::Task { list, .. } = &mut item.contents {
+                if list == old {
+                    *list = new.to_string();
+                }
             }
         }
         Ok(())
     }
 
+    /// Creates a copy of a list under a new name.
+    /// If `reset` is set, every task in the copy is marked as not started, as for a new checklist.
+    /// ### Returns
+    /// Result indicating success of the copy
+    pub fn clone_list(&mut self, source: &str, name: &str, reset: bool) -> Result<(), ListError> {
+        // confirm that the list exists and the new name is unique
+        if self.lists.contains_key(name) {
+            return Err(ListError::DuplicateListName {
+                name: name.to_string(),
+            });
+        }
+        let source = self
+            .lists
+            .get(source)
+            .ok_or_else(|| ListError::NonexistentListName {
+                name: source.to_string(),
+            })?;
+
+        // copy list, which is never archived even if the original was
+        let mut list = TodoList {
+            name: name.to_string(),
+            tasks: source.tasks.clone(),
+            next_id: source.next_id,
+            archived: false,
+        };
+        if reset {
+            list.tasks = list.tasks.iter().map(Task::reset).collect();
+        }
+        self.lists.insert(name.to_string(), list);
+
+        // set as focused list if no list is focused
+        if self.focused.is_none() {
+            self.focused = Some(name.to_string());
+        }
+        Ok(())
+    }
+
+    /// Archives or restores a list. Archived lists are kept but hidden, and cannot be focused.
+    /// ### Returns
+    /// Result indicating success of the change
+    pub fn set_archived(&mut self, name: &str, archived: bool) -> Result<(), ListError> {
+        // confirm that the list exists
+        let list = self
+            .lists
+            .get_mut(name)
+            .ok_or_else(|| ListError::NonexistentListName {
+                name: name.to_string(),
+            })?;
+        list.archived = archived;
+
+        // move focus off an archived list, or onto a restored one if nothing is focused
+        if self.focused.is_none() && !archived {
+            self.focused = Some(name.to_string());
+        }
+        self.refocus();
+        Ok(())
+    }
+
+    /// Moves or copies tasks, along with their subtasks, from one list to the end of another.
+    /// Tasks keep all of their details but get new IDs in the destination list. Dependencies
+    /// between tasks that travel together are kept, while those on tasks left behind are dropped.
+    /// ### Returns
+    /// Old and new paths of each transferred task, or ListError
+    pub fn transfer_tasks(
+        &mut self,
+        from: &str,
+        paths: Vec<TaskPath>,
+        to: &str,
+        keep_originals: bool,
+    ) -> Result<Vec<(TaskPath, TaskPath)>, ListError> {
+        // confirm that both lists exist
+        for name in [from, to] {
+            if !self.lists.contains_key(name) {
+                return Err(ListError::NonexistentListName {
+                    name: name.to_string(),
+                });
+            }
+        }
+
+        // subtasks of another selected task travel with it
+        let mut roots: Vec<TaskPath> = Vec::new();
+        for path in &paths {
+            if !roots.contains(path) && !path.ancestors().any(|a| paths.contains(&a)) {
+                roots.push(path.clone());
+            }
+        }
+
+        // take copies before changing anything, so a bad ID leaves both lists untouched
+        let source = &self.lists[from];
+        let tasks = roots
+            .iter()
+            .map(|path| source.get_task(path).cloned())
+            .collect::<Result<Vec<Task>, ListError>>()?;
+
+        if !keep_originals {
+            self.lists
+                .get_mut(from)
+                .unwrap()
+                .drop_tasks(roots.clone())?;
+        }
+        let destination = self.lists.get_mut(to).unwrap();
+        Ok(destination.insert_tasks(roots, tasks))
+    }
+
     /// Retrieves the number of lists available
     pub fn num_lists(&self) -> usize {
         self.lists.len()
@@ -133,11 +493,19 @@ impl ListFile {
     /// ### Returns
     /// Result indicating success of focus change
     pub fn shift_focus(&mut self, name: &str) -> Result<(), ListError> {
-        // confirm that the list exists
-        if !self.lists.contains_key(name) {
-            return Err(ListError::NonexistentListName {
-                name: name.to_string(),
-            });
+        // confirm that the list exists and is in use
+        match self.lists.get(name) {
+            None => {
+                return Err(ListError::NonexistentListName {
+                    name: name.to_string(),
+                })
+            }
+            Some(list) if list.archived => {
+                return Err(ListError::ArchivedList {
+                    name: name.to_string(),
+                })
+            }
+            Some(_) => {}
         }
 
         // shift focus
@@ -145,6 +513,36 @@ impl ListFile {
         Ok(())
     }
 
+    /// Returns immutable ref to the named TodoList, or the focused one if no name is given.
+    /// ### Returns
+    /// &TodoList or ListError
+    pub fn get_list(&self, name: Option<&str>) -> Result<&TodoList, ListError> {
+        match name {
+            Some(name) => self
+                .lists
+                .get(name)
+                .ok_or_else(|| ListError::NonexistentListName {
+                    name: name.to_string(),
+                }),
+            None => self.get_focused(),
+        }
+    }
+
+    /// Returns mutable ref to the named TodoList, or the focused one if no name is given.
+    /// ### Returns
+    /// &mut TodoList or ListError
+    pub fn get_mut_list(&mut self, name: Option<&str>) -> Result<&mut TodoList, ListError> {
+        match name {
+            Some(name) => self
+                .lists
+                .get_mut(name)
+                .ok_or_else(|| ListError::NonexistentListName {
+                    name: name.to_string(),
+                }),
+            None => self.get_mut_focused(),
+        }
+    }
+
     /// Returns immutable ref to the currently focused TodoList.
     /// ### Returns
     /// &TodoList or ListError
@@ -170,10 +568,35 @@ impl ListFile {
     }
 }
 
+/// Asks the user to confirm deleting the given list by re-typing its name.
+/// ### Returns
+/// Result indicating whether the name was re-typed correctly
+pub fn confirm_list_deletion(name: &str) -> Result<(), ListError> {
+    // prompt user to confirm delete
+    print!("Please confirm list deletion by re-typing the list name: ");
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    let input = input.trim(); // remove whitespace
+
+    // ensure input matches
+    if input != name {
+        return Err(ListError::FailedDeleteConfirmation {
+            entered: input.to_string(),
+            requested: name.to_string(),
+        });
+    }
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TodoList {
     pub name: String,
     pub tasks: Vec<Task>,
+    /// ID given to the next task added to this list
+    pub next_id: u32,
+    /// Archived lists are hidden from `ls` and `ts --all`
+    pub archived: bool,
 }
 
 impl TodoList {
@@ -181,104 +604,780 @@ impl TodoList {
         TodoList {
             name,
             tasks: Vec::new(),
+            next_id: 1,
+            archived: false,
         }
     }
 
-    /// Add task(s) to the todolist
-    pub fn add_tasks(&mut self, tasks: Vec<String>, date: Option<Date>) {
+    /// Add task(s) to the todolist, or as subtasks of `parent` if given.
+    /// `+tag` and `#tag` words in each title are moved into the task's tags.
+    /// ### Returns
+    /// Paths of the added tasks or ListError
+    pub fn add_tasks(
+        &mut self,
+        tasks: Vec<String>,
+        date: Option<Date>,
+        priority: Option<Priority>,
+        tags: &BTreeSet<String>,
+        recurrence: Option<Recurrence>,
+        parent: Option<&TaskPath>,
+    ) -> Result<Vec<TaskPath>, ListError> {
+        let (siblings, next_id) = match parent {
+            Some(parent) => {
+                self.children_mut(parent.ids())
+                    .ok_or_else(|| ListError::NonexistentTask {
+                        path: parent.clone(),
+                    })?
+            }
+            None => (&mut self.tasks, &mut self.next_id),
+        };
+
+        let mut added = Vec::new();
         for t in tasks {
-            self.tasks.push(Task {
-                title: t,
-                complete: false,
+            let (title, mut title_tags) = extract_tags(&t);
+            title_tags.extend(tags.iter().cloned());
+            siblings.push(Task {
+                id: *next_id,
+                title,
+                status: Status::Todo,
                 date,
-            })
+                priority,
+                tags: title_tags,
+                recurrence,
+                subtasks: Vec::new(),
+                next_subtask_id: 1,
+                after: BTreeSet::new(),
+                created: Some(Local::now().fixed_offset()),
+                started: None,
+                completed: None,
+                notes: String::new(),
+                spawned: None,
+            });
+            added.push(match parent {
+                Some(parent) => parent.child(*next_id),
+                None => TaskPath::top(*next_id),
+            });
+            *next_id += 1;
+        }
+        Ok(added)
+    }
+
+    /// Adds tasks taken from the given paths of another list as new top-level tasks.
+    /// ### Returns
+    /// Old and new paths of each task
+    fn insert_tasks(
+        &mut self,
+        origins: Vec<TaskPath>,
+        tasks: Vec<Task>,
+    ) -> Vec<(TaskPath, TaskPath)> {
+        let moved: Vec<(TaskPath, TaskPath)> = origins
+            .into_iter()
+            .zip(self.next_id..)
+            .map(|(origin, id)| (origin, TaskPath::top(id)))
+            .collect();
+
+        for (mut task, (_, path)) in tasks.into_iter().zip(&moved) {
+            task.id = path.ids()[0];
+            task.rebase_dependencies(&moved);
+            // the next occurrence of a closed recurring task stays behind with its old siblings
+            task.spawned = None;
+            self.tasks.push(task);
+            self.next_id += 1;
+        }
+        moved
+    }
+
+    /// Finds the tasks directly under the task at `parent`, or the top-level tasks if `parent`
+    /// is empty, along with the counter used to give them IDs.
+    fn children_mut(&mut self, parent: &[u32]) -> Option<(&mut Vec<Task>, &mut u32)> {
+        let Some((first, rest)) = parent.split_first() else {
+            return Some((&mut self.tasks, &mut self.next_id));
+        };
+
+        let mut task = self.tasks.iter_mut().find(|t| t.id == *first)?;
+        for id in rest {
+            task = task.subtasks.iter_mut().find(|t| t.id == *id)?;
         }
+        Some((&mut task.subtasks, &mut task.next_subtask_id))
     }
 
-    /// Helper func to sort, dedup, and reverse a list of usize.
-    /// Used when receiving multiple indices to remove from the task list.
-    /// Reversing the list allows for save deletion of elements while iterating over tasks.
-    fn sort_uniq_reverse(mut l: Vec<usize>) -> Vec<usize> {
-        // remove duplicate indices
-        l.sort();
-        l.dedup();
-        l.reverse(); // reverse so that indices don't change
+    /// Translates 1-based positions in the sorted list of top-level tasks into task paths,
+    /// in the order the positions were given. Positions are read one at a time, stopping at the
+    /// first invalid one, so huge ranges are rejected without being expanded.
+    /// ### Returns
+    /// Task paths, or ListError if any position is zero or past the end of the list
+    pub fn paths_from_positions(
+        &self,
+        positions: impl IntoIterator<Item = usize>,
+    ) -> Result<Vec<TaskPath>, ListError> {
+        let tasks = sorted(&self.tasks, SortKey::Date);
+
+        positions
+            .into_iter()
+            .map(|position| {
+                let i = position.checked_sub(1).ok_or(ListError::ZeroIndex)?;
+                tasks
+                    .get(i)
+                    .map(|t| TaskPath::top(t.id))
+                    .ok_or(ListError::PositionOutOfRange {
+                        position,
+                        len: tasks.len(),
+                    })
+            })
+            .collect()
+    }
+
+    /// Drop task(s) and their subtasks from the todolist.
+    /// Nothing is dropped if any of the tasks doesn't exist.
+    /// ### Returns
+    /// The dropped tasks along with the paths they were at, or ListError
+    pub fn drop_tasks(&mut self, paths: Vec<TaskPath>) -> Result<Vec<(TaskPath, Task)>, ListError> {
+        for path in &paths {
+            self.get_task(path)?;
+        }
+
+        // subtasks of a task dropped earlier are already gone along with it
+        let mut dropped = Vec::new();
+        for path in paths {
+            let (parent, id) = path.split();
+            if let Some((siblings, _)) = self.children_mut(parent) {
+                if let Some(i) = siblings.iter().position(|t| t.id == id) {
+                    dropped.push((path, siblings.remove(i)));
+                }
+            }
+        }
+        Ok(dropped)
+    }
 
-        // decrement each value
-        for i in l.iter_mut() {
-            *i -= 1;
+    /// Puts a dropped task back at its old path, or at the end of the list with a new ID if its
+    /// parent is gone.
+    /// ### Returns
+    /// Path the task was restored to
+    fn restore_task(&mut self, path: &TaskPath, mut task: Task) -> TaskPath {
+        let (parent, id) = path.split();
+        if let Some((siblings, next_id)) = self.children_mut(parent) {
+            if !siblings.iter().any(|t| t.id == id) {
+                siblings.push(task);
+                *next_id = (*next_id).max(id + 1);
+                return path.clone();
+            }
         }
-        l
+
+        task.id = self.next_id;
+        self.next_id += 1;
+        self.tasks.push(task);
+        TaskPath::top(self.next_id - 1)
     }
 
-    /// Drop task(s) from the todolist
-    pub fn drop_tasks(&mut self, mut index: Vec<usize>) {
-        index = Self::sort_uniq_reverse(index);
+    /// Moves task(s) to the given status.
+    /// Closing a recurring task (marking it done or cancelled) keeps it as a record and adds its
+    /// next occurrence, which takes over the recurrence rule. Reopening the record removes that
+    /// occurrence again and hands the rule back, as long as the occurrence hasn't been touched.
+    /// If `complete_parents` is set, parents whose subtasks are now all closed are marked done too.
+    /// Nothing is changed if any of the tasks doesn't exist.
+    /// ### Returns
+    /// Tasks whose status changed and next occurrences added or removed, or ListError
+    pub fn update_statuses(
+        &mut self,
+        paths: Vec<TaskPath>,
+        status: Status,
+        complete_parents: bool,
+    ) -> Result<StatusUpdates, ListError> {
+        for path in &paths {
+            self.get_task(path)?;
+        }
+
+        let mut updates = StatusUpdates::default();
+        for path in &paths {
+            if self.get_task(path)?.status != status {
+                updates.changed.push(path.clone());
+            }
+            self.set_status(path, status, &mut updates);
+        }
+
+        // walk up from each task, stopping at the first parent with unfinished subtasks
+        if status.is_closed() && complete_parents {
+            for path in &paths {
+                for ancestor in path.ancestors() {
+                    match self.get_task(&ancestor) {
+                        Ok(t)
+                            if !t.status.is_closed()
+                                && t.subtasks.iter().all(|s| s.status.is_closed()) =>
+                        {
+                            updates.changed.push(ancestor.clone());
+                            self.set_status(&ancestor, Status::Done, &mut updates)
+                        }
+                        Ok(t) if t.status.is_closed() => continue,
+                        _ => break,
+                    }
+                }
+            }
+        }
+        Ok(updates)
+    }
 
-        self.tasks.sort();
+    /// Moves a single task to the given status, adding or removing its next occurrence if needed.
+    fn set_status(&mut self, path: &TaskPath, status: Status, updates: &mut StatusUpdates) {
+        let (parent, id) = path.split();
+        let Some((siblings, next_id)) = self.children_mut(parent) else {
+            return;
+        };
+        let Some(index) = siblings.iter().position(|t| t.id == id) else {
+            return;
+        };
+        let sibling_path = |id| match path.ancestors().next() {
+            Some(parent) => parent.child(id),
+            None => TaskPath::top(id),
+        };
 
-        // drop them from the list
-        for i in index {
-            if i < self.tasks.len() {
-                self.tasks.remove(i);
+        // reopening a closed recurring task takes back the occurrence added when it was closed,
+        // unless that occurrence has since been worked on, changed or removed
+        let task = &siblings[index];
+        if !status.is_closed() && task.status.is_closed() {
+            if let Some(spawned) = task.spawned {
+                let untouched = siblings.iter().position(|t| {
+                    t.id == spawned
+                        && t.title == task.title
+                        && t.recurrence.is_some()
+                        && t.status == Status::Todo
+                });
+                match untouched {
+                    Some(i) => {
+                        let next = siblings.remove(i);
+                        let task = siblings.iter_mut().find(|t| t.id == id).unwrap();
+                        task.recurrence = next.recurrence;
+                        task.spawned = None;
+                        updates.withdrawn.push((sibling_path(next.id), next));
+                    }
+                    None => {
+                        // the occurrence is no longer this task's to take back, so forget it
+                        siblings[index].spawned = None;
+                        updates.kept.push(path.clone());
+                    }
+                }
             }
         }
+        let task = siblings.iter_mut().find(|t| t.id == id).unwrap();
+
+        // closing links the task to the occurrence it adds, if any, and drops any older link
+        let mut next = None;
+        if status.is_closed() && !task.status.is_closed() {
+            task.spawned = None;
+            next = task.recurrence.take().map(|r| task.next_occurrence(r));
+        }
+
+        // keep the first time work started, and when the task was last completed
+        let now = Local::now().fixed_offset();
+        match status {
+            Status::Todo => task.started = None,
+            Status::InProgress if task.started.is_none() => task.started = Some(now),
+            _ => {}
+        }
+        match status {
+            Status::Done if task.status != Status::Done => task.completed = Some(now),
+            Status::Done => {}
+            _ => task.completed = None,
+        }
+        task.status = status;
+
+        // give the new occurrence its own ID among its siblings
+        let Some(mut next) = next else {
+            return;
+        };
+        next.id = *next_id;
+        *next_id += 1;
+        task.spawned = Some(next.id);
+        siblings.push(next.clone());
+        updates.spawned.push((sibling_path(next.id), next));
+    }
+
+    /// Returns immutable ref to the task at the given path.
+    /// ### Returns
+    /// &Task or ListError
+    pub fn get_task(&self, path: &TaskPath) -> Result<&Task, ListError> {
+        let not_found = || ListError::NonexistentTask { path: path.clone() };
+        if path.ids().contains(&0) {
+            return Err(ListError::ZeroIndex);
+        }
+
+        let mut tasks = &self.tasks;
+        let mut found = None;
+        for id in path.ids() {
+            let task = tasks.iter().find(|t| t.id == *id).ok_or_else(not_found)?;
+            tasks = &task.subtasks;
+            found = Some(task);
+        }
+        found.ok_or_else(not_found)
     }
 
-    /// Update task(s) as complete or incomplete
-    pub fn update_completions(&mut self, mut index: Vec<usize>, complete: bool) {
-        index = Self::sort_uniq_reverse(index);
+    /// Replaces the notes of the task at the given path.
+    /// ### Returns
+    /// The updated &Task or ListError
+    pub fn set_notes(&mut self, path: &TaskPath, notes: &str) -> Result<&Task, ListError> {
+        let (parent, id) = path.split();
+        let task = self
+            .children_mut(parent)
+            .and_then(|(siblings, _)| siblings.iter_mut().find(|t| t.id == id))
+            .ok_or_else(|| ListError::NonexistentTask { path: path.clone() })?;
+
+        // trailing blank lines are left behind by most editors
+        task.notes = notes.trim_end().to_string();
+        Ok(task)
+    }
+
+    /// Applies the given changes to the task at the given path.
+    /// New dependencies must exist and may not lead back to the task.
+    /// ### Returns
+    /// The updated &Task or ListError
+    pub fn edit_task(&mut self, path: &TaskPath, changes: TaskChanges) -> Result<&Task, ListError> {
+        if let Some(after) = &changes.after {
+            for dep in after {
+                self.get_task(dep)?;
+                if dep == path || self.depends_on(dep, path) {
+                    return Err(ListError::DependencyCycle {
+                        task: path.clone(),
+                        dependency: dep.clone(),
+                    });
+                }
+            }
+        }
+
+        let (parent, id) = path.split();
+        let task = self
+            .children_mut(parent)
+            .and_then(|(siblings, _)| siblings.iter_mut().find(|t| t.id == id))
+            .ok_or_else(|| ListError::NonexistentTask { path: path.clone() })?;
 
-        self.tasks.sort();
+        if let Some(title) = changes.title {
+            task.title = title;
+        }
+        if let Some(date) = changes.date {
+            task.date = date;
+        }
+        if let Some(priority) = changes.priority {
+            task.priority = priority;
+        }
+        if let Some(tags) = changes.tags {
+            task.tags = tags;
+        }
+        if let Some(after) = changes.after {
+            task.after = after;
+        }
+        Ok(task)
+    }
 
-        // mark each task as complete
-        for i in index {
-            if i < self.tasks.len() {
-                self.tasks.get_mut(i).unwrap().complete = complete;
+    /// Returns whether `task` depends on `target`, directly or through other tasks.
+    fn depends_on(&self, task: &TaskPath, target: &TaskPath) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut stack = vec![task.clone()];
+        while let Some(path) = stack.pop() {
+            if !visited.insert(path.clone()) {
+                continue;
+            }
+            if let Ok(t) = self.get_task(&path) {
+                if t.after.contains(target) {
+                    return true;
+                }
+                stack.extend(t.after.iter().cloned());
             }
         }
+        false
+    }
+
+    /// Returns the unfinished tasks the given task is waiting on.
+    /// Dependencies which no longer exist are considered finished.
+    pub fn blockers<'a>(&self, task: &'a Task) -> Vec<&'a TaskPath> {
+        task.after
+            .iter()
+            .filter(|dep| self.get_task(dep).is_ok_and(|t| !t.status.is_closed()))
+            .collect()
     }
 
-    /// Print all tasks in the todolist
-    pub fn print_tasks(&self) {
-        // count digits in length of list to properly space indices
-        let digits = self.tasks.len().to_string().len();
+    /// Returns the paths of every task, subtasks included, which satisfies `matches`.
+    /// Paths are in display order.
+    pub fn select(&self, matches: impl Fn(&Task) -> bool) -> Vec<TaskPath> {
+        let mut rows = Vec::new();
+        collect_rows(
+            &self.tasks,
+            None,
+            0,
+            SortKey::Date,
+            &TaskFilter::default(),
+            &mut rows,
+        );
 
-        // sort tasks prior to printing
-        let mut tasks = self.tasks.clone();
-        tasks.sort();
+        rows.into_iter()
+            .filter(|(_, _, t)| matches(t))
+            .map(|(path, ..)| path)
+            .collect()
+    }
+
+    /// Returns the tasks which can be worked on right now: to do or in progress, not waiting on
+    /// other tasks, and without unfinished subtasks. Ordered by due date, then priority.
+    pub fn next_tasks(&self) -> Vec<(TaskPath, &Task)> {
+        let mut rows = Vec::new();
+        collect_rows(
+            &self.tasks,
+            None,
+            0,
+            SortKey::Date,
+            &TaskFilter::default(),
+            &mut rows,
+        );
+
+        let mut next: Vec<(TaskPath, &Task)> = rows
+            .into_iter()
+            .filter(|(_, _, t)| t.status.is_actionable() && self.blockers(t).is_empty())
+            .filter(|(_, _, t)| t.subtasks.iter().all(|s| s.status.is_closed()))
+            .map(|(path, _, t)| (path, t))
+            .collect();
+        next.sort_by_key(|(_, t)| *t);
+        next
+    }
+
+    /// Returns whether any task in the todolist passes the given filter.
+    pub fn has_matching(&self, filter: &TaskFilter) -> bool {
+        self.tasks.iter().any(|t| filter.matches_subtree(t))
+    }
+
+    /// Print tasks in the todolist which pass the given filter, along with their IDs.
+    /// Subtasks are indented below their parent.
+    pub fn print_tasks(&self, key: SortKey, filter: &TaskFilter) {
+        let mut rows = Vec::new();
+        collect_rows(&self.tasks, None, 0, key, filter, &mut rows);
+
+        // widest path determines how IDs are spaced
+        let width = rows
+            .iter()
+            .map(|(path, ..)| path.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let dim = std::io::stdout().is_terminal();
 
         println!("-- {} --", self.name);
-        for (i, t) in tasks.iter().enumerate() {
-            println!("{: <digits$}| {}", i + 1, t);
+        for (path, depth, task) in rows {
+            let line = format!(
+                "{: <width$}| {}{}",
+                path.to_string(),
+                "  ".repeat(depth),
+                task
+            );
+
+            // blocked tasks are marked with what they wait on, and dimmed in a terminal
+            let blockers = self.blockers(task);
+            if blockers.is_empty() || task.status.is_closed() {
+                println!("{}", line);
+            } else {
+                let blockers: Vec<String> = blockers.iter().map(|p| p.to_string()).collect();
+                let line = format!("{} (blocked by {})", line, blockers.join(", "));
+                if dim {
+                    println!("\x1b[2m{}\x1b[0m", line);
+                } else {
+                    println!("{}", line);
+                }
+            }
+        }
+    }
+
+    /// Print everything known about the task at the given path.
+    /// ### Returns
+    /// Result indicating whether the task exists
+    pub fn print_task(&self, path: &TaskPath) -> Result<(), ListError> {
+        let task = self.get_task(path)?;
+        let field =
+            |name: &str, value: String| println!("  {: <10} {}", format!("{}:", name), value);
+        let timestamp =
+            |t: DateTime<FixedOffset>| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+
+        println!("{}| {}", path, task);
+        if let Some(status) = task.status.to_possible_value() {
+            field("status", status.get_name().to_string());
+        }
+        if let Some(date) = task.date {
+            field("due", date.to_string());
+        }
+        if let Some(priority) = task.priority.and_then(|p| p.to_possible_value()) {
+            field("priority", priority.get_name().to_string());
+        }
+        if !task.tags.is_empty() {
+            field(
+                "tags",
+                task.tags.iter().cloned().collect::<Vec<_>>().join(" "),
+            );
+        }
+        if let Some(r) = task.recurrence {
+            let from = match r.from {
+                RecurFrom::Due => "due date",
+                RecurFrom::Completion => "completion",
+            };
+            field("repeats", format!("every {}, from {}", r.every, from));
+        }
+        if !task.after.is_empty() {
+            let after: Vec<String> = task.after.iter().map(|p| p.to_string()).collect();
+            field("after", after.join(", "));
+        }
+        if !task.subtasks.is_empty() {
+            let done = task
+                .subtasks
+                .iter()
+                .filter(|t| t.status.is_closed())
+                .count();
+            field(
+                "subtasks",
+                format!("{}/{} finished", done, task.subtasks.len()),
+            );
+        }
+        if let Some(t) = task.created {
+            field("created", timestamp(t));
+        }
+        if let Some(t) = task.started {
+            field("started", timestamp(t));
+        }
+        if let Some(t) = task.completed {
+            field("completed", timestamp(t));
+        }
+        let links = task.links();
+        if !links.is_empty() {
+            field("links", links.join("\n             "));
+        }
+        if !task.notes.is_empty() {
+            println!();
+            for line in task.notes.lines() {
+                println!("  {}", line);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns refs to the tasks in the given order.
+fn sorted(tasks: &[Task], key: SortKey) -> Vec<&Task> {
+    let mut tasks: Vec<&Task> = tasks.iter().collect();
+    match key {
+        SortKey::Date => tasks.sort(),
+        SortKey::Priority => tasks
+            .sort_by(|a, b| cmp_priority(a.priority, b.priority).then_with(|| a.date.cmp(&b.date))),
+        SortKey::Created => tasks.sort_by(|a, b| a.created.cmp(&b.created).then_with(|| a.cmp(b))),
+        SortKey::Completed => {
+            tasks.sort_by(|a, b| b.completed.cmp(&a.completed).then_with(|| a.cmp(b)))
         }
     }
+    tasks
+}
+
+/// Flattens tasks and their subtasks into display order as `(path, depth, task)` rows.
+fn collect_rows<'a>(
+    tasks: &'a [Task],
+    parent: Option<&TaskPath>,
+    depth: usize,
+    key: SortKey,
+    filter: &TaskFilter,
+    rows: &mut Vec<(TaskPath, usize, &'a Task)>,
+) {
+    for t in sorted(tasks, key) {
+        if !filter.matches_subtree(t) {
+            continue;
+        }
+        let path = match parent {
+            Some(parent) => parent.child(t.id),
+            None => TaskPath::top(t.id),
+        };
+        rows.push((path.clone(), depth, t));
+        collect_rows(&t.subtasks, Some(&path), depth + 1, key, filter, rows);
+    }
+}
+
+/// Order in which tasks are displayed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Earliest due date first, with priority breaking ties
+    #[default]
+    Date,
+    /// Most urgent first, with due date breaking ties
+    Priority,
+    /// Oldest first, starting with tasks added before creation times were recorded
+    Created,
+    /// Most recently completed first, with unfinished tasks last
+    Completed,
+}
+
+/// Criteria a task must meet to be listed.
+/// The default filter matches every task.
+#[derive(Debug, Default)]
+pub struct TaskFilter {
+    /// Tags which must all be present on the task
+    pub tags: BTreeSet<String>,
+    /// Only tasks marked done at or after this time
+    pub done_since: Option<DateTime<FixedOffset>>,
+}
+
+impl TaskFilter {
+    /// Returns whether the task meets every criterion of the filter.
+    pub fn matches(&self, task: &Task) -> bool {
+        self.tags.is_subset(&task.tags)
+            && self
+                .done_since
+                .is_none_or(|since| task.completed.is_some_and(|c| c >= since))
+    }
+
+    /// Returns whether the task or any of its subtasks meets the filter.
+    pub fn matches_subtree(&self, task: &Task) -> bool {
+        self.matches(task) || task.subtasks.iter().any(|t| self.matches_subtree(t))
+    }
+
+    /// Returns whether the filter excludes any tasks at all.
+    pub fn is_active(&self) -> bool {
+        !self.tags.is_empty() || self.done_since.is_some()
+    }
+}
+
+/// Outcome of `TodoList::update_statuses`.
+#[derive(Debug, Default)]
+pub struct StatusUpdates {
+    /// Tasks whose status changed, including parents completed along with their subtasks
+    pub changed: Vec<TaskPath>,
+    /// Next occurrences added for closed recurring tasks
+    pub spawned: Vec<(TaskPath, Task)>,
+    /// Next occurrences removed again because the recurring task they followed was reopened
+    pub withdrawn: Vec<(TaskPath, Task)>,
+    /// Reopened recurring tasks whose next occurrence had been touched, so was left in place
+    pub kept: Vec<TaskPath>,
+}
+
+/// Changes to apply to a task with `TodoList::edit_task`.
+/// Fields left as `None` are not changed, while `Some(None)` clears an optional field.
+#[derive(Debug, Default)]
+pub struct TaskChanges {
+    pub title: Option<String>,
+    pub date: Option<Option<Date>>,
+    pub priority: Option<Option<Priority>>,
+    pub tags: Option<BTreeSet<String>>,
+    pub after: Option<BTreeSet<TaskPath>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq)]
 pub struct Task {
+    /// Stable identifier, unique within its TodoList
+    pub id: u32,
     pub title: String,
     pub date: Option<Date>,
-    pub complete: bool,
+    pub priority: Option<Priority>,
+    pub tags: BTreeSet<String>,
+    pub recurrence: Option<Recurrence>,
+    pub status: Status,
+    pub subtasks: Vec<Task>,
+    /// ID given to the next subtask added to this task
+    pub next_subtask_id: u32,
+    /// Tasks in the same list which must be completed before this one
+    pub after: BTreeSet<TaskPath>,
+    /// When the task was added, unknown for tasks added before this was recorded
+    pub created: Option<DateTime<FixedOffset>>,
+    /// When work on the task first started
+    pub started: Option<DateTime<FixedOffset>>,
+    /// When the task was marked done
+    pub completed: Option<DateTime<FixedOffset>>,
+    /// Free-form details such as links or acceptance criteria, empty if there are none
+    pub notes: String,
+    /// ID of the next occurrence among this task's siblings, added when this recurring task
+    /// was closed
+    pub spawned: Option<u32>,
+}
+
+impl Task {
+    /// Creates the next, not yet started occurrence of this task following its recurrence rule.
+    /// The returned task still needs an ID from its list.
+    fn next_occurrence(&self, recurrence: Recurrence) -> Task {
+        let today = Local::now().date_naive();
+        let base = match (recurrence.from, self.date) {
+            (RecurFrom::Due, Some(date)) => date.day(),
+            _ => today,
+        };
+        let day = recurrence.every.next_after(base);
+
+        Task {
+            status: Status::Todo,
+            date: Some(match self.date {
+                Some(date) => date.on_day(day),
+                None => Date::new(day),
+            }),
+            recurrence: Some(recurrence),
+            subtasks: self.subtasks.iter().map(Task::reset).collect(),
+            created: Some(Local::now().fixed_offset()),
+            started: None,
+            completed: None,
+            spawned: None,
+            ..self.clone()
+        }
+    }
+
+    /// Points the dependencies of this task and its subtasks at the new paths of moved tasks,
+    /// given as `(old, new)` pairs. Dependencies on tasks which did not move are dropped.
+    fn rebase_dependencies(&mut self, moved: &[(TaskPath, TaskPath)]) {
+        self.after = self
+            .after
+            .iter()
+            .filter_map(|dep| moved.iter().find_map(|(old, new)| dep.rebase(old, new)))
+            .collect();
+        for subtask in &mut self.subtasks {
+            subtask.rebase_dependencies(moved);
+        }
+    }
+
+    /// URLs mentioned in the task's notes, in the order they appear.
+    pub fn links(&self) -> Vec<&str> {
+        let regex = Regex::new(r#"https?://[^\s<>"]+"#).unwrap();
+        regex
+            .find_iter(&self.notes)
+            // punctuation directly after a link usually ends the sentence around it
+            .map(|m| {
+                m.as_str()
+                    .trim_end_matches(['.', ',', ';', ':', '!', '?', ')'])
+            })
+            .collect()
+    }
+
+    /// Fresh copy of this task and its subtasks, all marked as not started.
+    fn reset(&self) -> Task {
+        Task {
+            status: Status::Todo,
+            created: Some(Local::now().fixed_offset()),
+            started: None,
+            completed: None,
+            spawned: None,
+            subtasks: self.subtasks.iter().map(Task::reset).collect(),
+            ..self.clone()
+        }
+    }
 }
 
 impl Display for Task {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.date {
-            Some(d) => write!(
-                f,
-                "{} [{}] {}",
-                if self.complete { "✓" } else { "✕" },
-                d,
-                self.title
-            ),
-            None => write!(
-                f,
-                "{} {}",
-                if self.complete { "✓" } else { "✕" },
-                self.title
-            ),
+        write!(f, "{}", self.status)?;
+        if let Some(p) = self.priority {
+            write!(f, " {}", p)?;
+        }
+        if let Some(d) = self.date {
+            write!(f, " [{}]", d)?;
+        }
+        write!(f, " {}", self.title)?;
+        for tag in &self.tags {
+            write!(f, " #{}", tag)?;
+        }
+        if !self.subtasks.is_empty() {
+            let done = self
+                .subtasks
+                .iter()
+                .filter(|t| t.status.is_closed())
+                .count();
+            write!(f, " ({}/{})", done, self.subtasks.len())?;
         }
+        if let Some(r) = self.recurrence {
+            write!(f, " (every {})", r.every)?;
+        }
+        if !self.notes.is_empty() {
+            write!(f, " ✎")?;
+        }
+        Ok(())
     }
 }
 
@@ -286,7 +1385,19 @@ impl Display for Task {
 
 impl PartialEq for Task {
     fn eq(&self, other: &Self) -> bool {
-        self.title == other.title && self.date == other.date && self.complete == other.complete
+        self.id == other.id
+            && self.title == other.title
+            && self.date == other.date
+            && self.priority == other.priority
+            && self.tags == other.tags
+            && self.recurrence == other.recurrence
+            && self.subtasks == other.subtasks
+            && self.after == other.after
+            && self.created == other.created
+            && self.started == other.started
+            && self.completed == other.completed
+            && self.notes == other.notes
+            && self.status == other.status
     }
 }
 impl PartialOrd for Task {
@@ -296,26 +1407,511 @@ impl PartialOrd for Task {
 }
 impl Ord for Task {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.date.cmp(&other.date)
+        self.date
+            .cmp(&other.date)
+            .then_with(|| cmp_priority(self.priority, other.priority))
     }
 }
 
 pub mod errors {
     use thiserror::Error;
 
+    use crate::utils::task_ref::TaskPath;
+
     #[derive(Error, Debug)]
     pub enum ListError {
-        /// Attempting to create a list with a used name
-        #[error("Cannot create list named {name:?}, a list already exists with this name.")]
+        /// Attempting to create or rename a list with a used name
+        #[error("Cannot use list name {name:?}, a list already exists with this name.")]
         DuplicateListName { name: String },
-        /// Attempting to delete a list which doesn't exist
-        #[error("Cannot delete list named {name:?}, no such list exists")]
+        /// Attempting to use a list which doesn't exist
+        #[error("No list named {name:?} exists")]
         NonexistentListName { name: String },
         /// Deletion confirmation did not match
         #[error("Cannot delete list; List name entered {entered:?} does not match requested deletion {requested:?}.")]
         FailedDeleteConfirmation { entered: String, requested: String },
+        /// Attempting to restore an item which isn't in the trash
+        #[error("No item with ID {id} is in the trash")]
+        NonexistentTrashItem { id: u32 },
+        /// Attempting to focus a list which has been archived
+        #[error("List {name:?} is archived, use `todo unarchive {name}` to restore it.")]
+        ArchivedList { name: String },
         /// Attempted to get focused list when no list is focused
         #[error("Cannot get focused list; there is none.")]
         NoFocusedList,
+        /// Attempting to access a task which doesn't exist
+        #[error("No task with ID {path} exists in this list")]
+        NonexistentTask { path: TaskPath },
+        /// Task IDs and positions are 1-based, so 0 never refers to a task
+        #[error("Task IDs and positions start at 1, 0 does not refer to a task")]
+        ZeroIndex,
+        /// Attempting to use a position past the end of the displayed list
+        #[error("No task at position {position}, {}", valid_positions(*.len))]
+        PositionOutOfRange { position: usize, len: usize },
+        /// Positions only count top-level tasks, so they have no subtask part
+        #[error("{given} is not a position; positions only refer to top-level tasks, so leave out --position to use subtask IDs")]
+        SubtaskPosition { given: TaskPath },
+        /// Selection given on the command line picked out no tasks
+        #[error("No tasks matched the given selection")]
+        NoMatchingTasks,
+        /// Adding a dependency would make a task wait on itself
+        #[error(
+            "Task {task} cannot wait on task {dependency}, as it would end up waiting on itself"
+        )]
+        DependencyCycle {
+            task: TaskPath,
+            dependency: TaskPath,
+        },
+        /// ListFile could not be read or deserialized
+        #[error("Failed to read ListFile {path:?}: {error}")]
+        UnreadableListFile { path: String, error: String },
+        /// Another `todo` process held the ListFile lock for too long
+        #[error("Timed out after {seconds}s waiting for lock {path:?}; another todo process is using your lists.")]
+        LockTimeout { path: String, seconds: u64 },
+        /// Lock file could not be created or locked
+        #[error("Failed to lock {path:?}: {error}")]
+        LockFailed { path: String, error: String },
+        /// ListFile was written by a newer version of `todo`
+        #[error("ListFile {path:?} uses format v{found}, but this version of todo only supports up to v{supported}. Please upgrade todo.")]
+        UnsupportedVersion {
+            path: String,
+            found: u32,
+            supported: u32,
+        },
+    }
+
+    /// Describes the positions which are valid in a list showing `len` top-level tasks.
+    fn valid_positions(len: usize) -> String {
+        match len {
+            0 => "the list has no tasks".to_string(),
+            1 => "the only valid position is 1".to_string(),
+            _ => format!("valid positions are 1 to {}", len),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::recur::Interval;
+
+    fn path(s: &str) -> TaskPath {
+        crate::utils::task_ref::parse_task_path(s).unwrap()
+    }
+
+    /// List with a task for each title, with IDs counting up from 1.
+    fn list_of(titles: &[&str]) -> TodoList {
+        let mut list = TodoList::new("work".to_string());
+        list.add_tasks(
+            titles.iter().map(|t| t.to_string()).collect(),
+            None,
+            None,
+            &BTreeSet::new(),
+            None,
+            None,
+        )
+        .unwrap();
+        list
+    }
+
+    fn wait_on(list: &mut TodoList, task: &str, deps: &[&str]) -> Result<(), ListError> {
+        let changes = TaskChanges {
+            after: Some(deps.iter().map(|d| path(d)).collect()),
+            ..TaskChanges::default()
+        };
+        list.edit_task(&path(task), changes).map(|_| ())
+    }
+
+    /// List with a single task `water plants` which repeats every week.
+    fn recurring() -> TodoList {
+        let mut list = TodoList::new("home".to_string());
+        let weekly = Recurrence {
+            every: Interval::Weeks(1),
+            from: RecurFrom::Due,
+        };
+        list.add_tasks(
+            vec!["water plants".to_string()],
+            None,
+            None,
+            &BTreeSet::new(),
+            Some(weekly),
+            None,
+        )
+        .unwrap();
+        list
+    }
+
+    fn set(list: &mut TodoList, s: &str, status: Status) -> StatusUpdates {
+        list.update_statuses(vec![path(s)], status, false).unwrap()
+    }
+
+    fn ids(list: &TodoList) -> Vec<u32> {
+        list.tasks.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn closing_a_recurring_task_adds_its_next_occurrence() {
+        let mut list = recurring();
+        let updates = set(&mut list, "1", Status::Done);
+
+        assert_eq!(updates.changed, [path("1")]);
+        assert_eq!(updates.spawned.len(), 1);
+        assert_eq!(updates.spawned[0].0, path("2"));
+        let done = list.get_task(&path("1")).unwrap();
+        assert_eq!((done.recurrence, done.spawned), (None, Some(2)));
+        let next = list.get_task(&path("2")).unwrap();
+        assert_eq!(next.status, Status::Todo);
+        assert!(next.recurrence.is_some() && next.date.is_some());
+    }
+
+    #[test]
+    fn reopening_takes_back_an_untouched_occurrence() {
+        let mut list = recurring();
+        set(&mut list, "1", Status::Done);
+        let updates = set(&mut list, "1", Status::Todo);
+
+        assert_eq!(updates.withdrawn.len(), 1);
+        assert_eq!(updates.withdrawn[0].0, path("2"));
+        assert!(updates.kept.is_empty());
+        assert_eq!(ids(&list), [1]);
+        let task = list.get_task(&path("1")).unwrap();
+        assert!(task.recurrence.is_some());
+        assert_eq!(task.spawned, None);
+    }
+
+    #[test]
+    fn reopening_keeps_an_occurrence_which_was_worked_on() {
+        let mut list = recurring();
+        set(&mut list, "1", Status::Done);
+        set(&mut list, "2", Status::InProgress);
+        let updates = set(&mut list, "1", Status::Todo);
+
+        assert!(updates.withdrawn.is_empty());
+        assert_eq!(updates.kept, [path("1")]);
+        assert_eq!(ids(&list), [1, 2]);
+        let task = list.get_task(&path("1")).unwrap();
+        assert_eq!((task.recurrence, task.spawned), (None, None));
+    }
+
+    #[test]
+    fn kept_occurrences_are_never_taken_back_later() {
+        let mut list = recurring();
+        set(&mut list, "1", Status::Done);
+        set(&mut list, "2", Status::Done);
+        assert_eq!(set(&mut list, "1", Status::Todo).kept, [path("1")]);
+
+        // 1 no longer repeats, so closing it again adds nothing
+        assert!(set(&mut list, "1", Status::Done).spawned.is_empty());
+        assert_eq!(set(&mut list, "2", Status::Todo).withdrawn.len(), 1);
+        let updates = set(&mut list, "1", Status::Todo);
+
+        assert!(updates.withdrawn.is_empty() && updates.kept.is_empty());
+        assert_eq!(ids(&list), [1, 2]);
+    }
+
+    #[test]
+    fn closing_without_a_new_occurrence_clears_stale_links() {
+        let mut list = recurring();
+        list.add_tasks(
+            vec!["water plants".to_string()],
+            None,
+            None,
+            &BTreeSet::new(),
+            None,
+            None,
+        )
+        .unwrap();
+        // 2 looks like an untouched occurrence of 1, but was not added by closing it
+        list.tasks[1].recurrence = list.tasks[0].recurrence.take();
+        list.tasks[0].spawned = Some(2);
+
+        set(&mut list, "1", Status::Cancelled);
+        assert_eq!(list.get_task(&path("1")).unwrap().spawned, None);
+        assert!(set(&mut list, "1", Status::Todo).withdrawn.is_empty());
+        assert_eq!(ids(&list), [1, 2]);
+    }
+
+    #[test]
+    fn reset_copies_forget_their_occurrences() {
+        let mut list_file = ListFile::new();
+        list_file.lists.insert("a".to_string(), recurring());
+        set(list_file.lists.get_mut("a").unwrap(), "1", Status::Done);
+        list_file.clone_list("a", "b", true).unwrap();
+
+        let copy = list_file.lists.get_mut("b").unwrap();
+        assert_eq!(copy.get_task(&path("1")).unwrap().spawned, None);
+        set(copy, "1", Status::Done);
+        assert!(set(copy, "1", Status::Todo).withdrawn.is_empty());
+        assert_eq!(ids(copy), [1, 2]);
+    }
+
+    #[test]
+    fn rejects_waiting_on_itself() {
+        let mut list = list_of(&["a", "b", "c"]);
+        assert!(matches!(
+            wait_on(&mut list, "3", &["3"]),
+            Err(ListError::DependencyCycle { .. })
+        ));
+        assert!(list.get_task(&path("3")).unwrap().after.is_empty());
+    }
+
+    #[test]
+    fn rejects_indirect_cycles() {
+        let mut list = list_of(&["a", "b", "c", "d", "e"]);
+        wait_on(&mut list, "4", &["3"]).unwrap();
+        assert!(matches!(
+            wait_on(&mut list, "3", &["4"]),
+            Err(ListError::DependencyCycle { task, dependency })
+                if task == path("3") && dependency == path("4")
+        ));
+
+        wait_on(&mut list, "5", &["4"]).unwrap();
+        assert!(matches!(
+            wait_on(&mut list, "3", &["1", "5"]),
+            Err(ListError::DependencyCycle { .. })
+        ));
+        assert!(list.get_task(&path("3")).unwrap().after.is_empty());
+        wait_on(&mut list, "3", &["1", "2"]).unwrap();
+    }
+
+    #[test]
+    fn rejects_missing_dependencies() {
+        let mut list = list_of(&["a", "b"]);
+        assert!(matches!(
+            wait_on(&mut list, "1", &["9"]),
+            Err(ListError::NonexistentTask { path: p }) if p == path("9")
+        ));
+        assert!(matches!(
+            wait_on(&mut list, "1", &["2.1"]),
+            Err(ListError::NonexistentTask { .. })
+        ));
+    }
+
+    #[test]
+    fn next_skips_blocked_tasks_and_tasks_with_open_subtasks() {
+        let mut list = list_of(&["design", "build", "ship", "old"]);
+        list.add_tasks(
+            vec!["checklist".to_string()],
+            None,
+            None,
+            &BTreeSet::new(),
+            None,
+            Some(&path("3")),
+        )
+        .unwrap();
+        wait_on(&mut list, "2", &["1"]).unwrap();
+        set(&mut list, "4", Status::Done);
+
+        let next = |list: &TodoList| {
+            let mut paths = list
+                .next_tasks()
+                .into_iter()
+                .map(|(path, _)| path.to_string())
+                .collect::<Vec<String>>();
+            paths.sort();
+            paths
+        };
+        assert_eq!(next(&list), ["1", "3.1"]);
+        let build = list.get_task(&path("2")).unwrap();
+        assert_eq!(list.blockers(build), [&path("1")]);
+
+        set(&mut list, "1", Status::Done);
+        set(&mut list, "3.1", Status::Cancelled);
+        assert_eq!(next(&list), ["2", "3"]);
+        let build = list.get_task(&path("2")).unwrap();
+        assert!(list.blockers(build).is_empty());
+    }
+
+    /// Lists `a` with `design`, `build` waiting on `design`, and `ship` whose subtasks `test`
+    /// and `release` wait on `design` and `test`, and `b` with a single task.
+    fn transfer_file() -> ListFile {
+        let mut a = list_of(&["design", "build", "ship"]);
+        a.add_tasks(
+            vec!["test".to_string(), "release".to_string()],
+            None,
+            None,
+            &BTreeSet::new(),
+            None,
+            Some(&path("3")),
+        )
+        .unwrap();
+        wait_on(&mut a, "2", &["1"]).unwrap();
+        wait_on(&mut a, "3.1", &["1"]).unwrap();
+        wait_on(&mut a, "3.2", &["3.1"]).unwrap();
+
+        let mut list_file = ListFile::new();
+        list_file.lists.insert("a".to_string(), a);
+        list_file.lists.insert("b".to_string(), list_of(&["x"]));
+        list_file
+    }
+
+    fn after(list_file: &ListFile, list: &str, task: &str) -> Vec<String> {
+        let task = list_file.lists[list].get_task(&path(task)).unwrap();
+        task.after.iter().map(TaskPath::to_string).collect()
+    }
+
+    #[test]
+    fn rebases_dependencies_onto_moved_paths() {
+        let mut task = list_of(&["a"]).tasks.remove(0);
+        task.after = [path("3.1"), path("3"), path("1"), path("4.1")].into();
+        task.rebase_dependencies(&[(path("3"), path("5")), (path("4.1"), path("6"))]);
+        assert_eq!(task.after, [path("5.1"), path("5"), path("6")].into());
+    }
+
+    #[test]
+    fn moving_keeps_dependencies_between_tasks_moved_together() {
+        let mut list_file = transfer_file();
+        let moved = list_file
+            .transfer_tasks("a", vec![path("2"), path("1")], "b", false)
+            .unwrap();
+
+        assert_eq!(moved, [(path("2"), path("2")), (path("1"), path("3"))]);
+        assert_eq!(after(&list_file, "b", "2"), ["3"]);
+        assert_eq!(ids(&list_file.lists["a"]), [3]);
+    }
+
+    #[test]
+    fn moving_subtasks_with_their_parent_rebases_their_dependencies() {
+        let mut list_file = transfer_file();
+        let moved = list_file
+            .transfer_tasks("a", vec![path("3.2"), path("3")], "b", false)
+            .unwrap();
+
+        // 3.2 travels inside 3 rather than on its own
+        assert_eq!(moved, [(path("3"), path("2"))]);
+        assert_eq!(after(&list_file, "b", "2.2"), ["2.1"]);
+        // design stayed behind, so the subtask no longer waits on it
+        assert!(after(&list_file, "b", "2.1").is_empty());
+        assert_eq!(ids(&list_file.lists["a"]), [1, 2]);
+    }
+
+    #[test]
+    fn copying_leaves_the_original_tasks_alone() {
+        let mut list_file = transfer_file();
+        list_file
+            .transfer_tasks("a", vec![path("2")], "b", true)
+            .unwrap();
+
+        assert_eq!(ids(&list_file.lists["a"]), [1, 2, 3]);
+        assert_eq!(after(&list_file, "a", "2"), ["1"]);
+        assert!(after(&list_file, "b", "2").is_empty());
+    }
+
+    #[test]
+    fn bad_ids_leave_both_lists_unchanged() {
+        let mut list_file = transfer_file();
+        let before = serde_json::to_value(&list_file.lists).unwrap();
+
+        assert!(matches!(
+            list_file.transfer_tasks("a", vec![path("1"), path("9")], "b", false),
+            Err(ListError::NonexistentTask { .. })
+        ));
+        assert!(matches!(
+            list_file.transfer_tasks("a", vec![path("1")], "c", false),
+            Err(ListError::NonexistentListName { .. })
+        ));
+        assert_eq!(serde_json::to_value(&list_file.lists).unwrap(), before);
+    }
+
+    /// Lists `work` with `a`, `b` with subtask `kid`, and `c`, and an empty `home`.
+    fn trash_file() -> ListFile {
+        let mut work = list_of(&["a", "b", "c"]);
+        work.add_tasks(
+            vec!["kid".to_string()],
+            None,
+            None,
+            &BTreeSet::new(),
+            None,
+            Some(&path("2")),
+        )
+        .unwrap();
+
+        let mut list_file = ListFile::new();
+        list_file.lists.insert("work".to_string(), work);
+        list_file.create_list("home").unwrap();
+        list_file
+    }
+
+    /// Drops a task from `work` and returns its trash ID.
+    fn trash(list_file: &mut ListFile, task: &str) -> u32 {
+        let work = list_file.lists.get_mut("work").unwrap();
+        let dropped = work.drop_tasks(vec![path(task)]).unwrap();
+        list_file.trash_tasks("work", dropped)[0]
+    }
+
+    fn title(list_file: &ListFile, task: &str) -> String {
+        list_file.lists["work"]
+            .get_task(&path(task))
+            .unwrap()
+            .title
+            .clone()
+    }
+
+    #[test]
+    fn restores_tasks_to_their_original_path() {
+        let mut list_file = trash_file();
+        let id = trash(&mut list_file, "2");
+        assert!(list_file.lists["work"].get_task(&path("2")).is_err());
+
+        assert_eq!(list_file.restore(id).unwrap(), "task work:2| ✕ b (0/1)");
+        assert_eq!(title(&list_file, "2.1"), "kid");
+        assert!(list_file.trash.is_empty());
+        assert_eq!(list_file.lists["work"].next_id, 4);
+    }
+
+    #[test]
+    fn restores_subtasks_of_removed_parents_at_the_top_level() {
+        let mut list_file = trash_file();
+        let kid = trash(&mut list_file, "2.1");
+        let parent = trash(&mut list_file, "2");
+
+        assert!(list_file.restore(kid).unwrap().starts_with("task work:4|"));
+        assert_eq!(title(&list_file, "4"), "kid");
+        list_file.restore(parent).unwrap();
+        assert_eq!(title(&list_file, "2"), "b");
+        assert_eq!(list_file.lists["work"].next_id, 5);
+    }
+
+    #[test]
+    fn restoring_into_a_deleted_list_fails() {
+        let mut list_file = trash_file();
+        let task = trash(&mut list_file, "1");
+        let list = list_file.delete_list("work").unwrap();
+
+        assert!(matches!(
+            list_file.restore(task),
+            Err(ListError::NonexistentListName { name }) if name == "work"
+        ));
+        assert_eq!(list_file.trash.len(), 2);
+
+        // bringing the list back gives the task somewhere to go
+        list_file.restore(list).unwrap();
+        list_file.restore(task).unwrap();
+        assert_eq!(title(&list_file, "1"), "a");
+    }
+
+    #[test]
+    fn restoring_a_list_whose_name_is_taken_fails() {
+        let mut list_file = trash_file();
+        let id = list_file.delete_list("home").unwrap();
+        list_file.create_list("home").unwrap();
+
+        assert!(matches!(
+            list_file.restore(id),
+            Err(ListError::DuplicateListName { name }) if name == "home"
+        ));
+        assert_eq!(list_file.trash.len(), 1);
+        assert!(matches!(
+            list_file.restore(id + 1),
+            Err(ListError::NonexistentTrashItem { .. })
+        ));
+    }
+
+    #[test]
+    fn restores_tasks_into_renamed_lists() {
+        let mut list_file = trash_file();
+        let id = trash(&mut list_file, "3");
+        list_file.rename_list("work", "job").unwrap();
+
+        assert!(list_file.restore(id).unwrap().starts_with("task job:3|"));
     }
 }
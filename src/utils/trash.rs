@@ -0,0 +1,53 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{
+    task_ref::TaskPath,
+    todolist::{Task, TodoList},
+};
+
+/// Number of days items are kept in the trash when no retention period is configured.
+pub const DEFAULT_TRASH_DAYS: u32 = 30;
+
+/// A dropped task or deleted list, kept so it can be restored.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashItem {
+    /// Identifier, unique within the trash
+    pub id: u32,
+    pub deleted: DateTime<FixedOffset>,
+    pub contents: Trashed,
+}
+
+/// What was thrown away, along with where it came from.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Trashed {
+    /// A task and its subtasks, dropped from the given path of a list
+    Task {
+        list: String,
+        path: TaskPath,
+        task: Box<Task>,
+    },
+    /// A whole list
+    List { list: TodoList },
+}
+
+impl TrashItem {
+    /// Returns whether the item has been in the trash for longer than `days`.
+    pub fn is_expired(&self, days: u32) -> bool {
+        Local::now().fixed_offset() - self.deleted > chrono::Duration::days(days as i64)
+    }
+}
+
+impl Display for Trashed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trashed::Task { list, path, task } => write!(f, "task {}:{}| {}", list, path, task),
+            Trashed::List { list } => {
+                write!(f, "list '{}' ({} tasks)", list.name, list.tasks.len())
+            }
+        }
+    }
+}